@@ -11,7 +11,7 @@
 
   [ "$status" -eq 0 ]
   [ $(expr "$output" : '.*"allowed":false.*') -ne 0 ]
-  [ $(expr "$output" : '.*"message":".*tags not allowed: latest.*') -ne 0 ]
+  [ $(expr "$output" : '.*"message":".*tag latest not allowed.*') -ne 0 ]
 }
 
 @test "Pod: reject implicit latest tag" {
@@ -25,7 +25,7 @@
 
   [ "$status" -eq 0 ]
   [ $(expr "$output" : '.*"allowed":false.*') -ne 0 ]
-  [ $(expr "$output" : '.*"message":".*tags not allowed: latest.*') -ne 0 ]
+  [ $(expr "$output" : '.*"message":".*tag latest not allowed.*') -ne 0 ]
 }
 
 @test "CronJob: accept image from allowed registry" {
@@ -52,7 +52,7 @@
 
   [ "$status" -eq 0 ]
   [ $(expr "$output" : '.*"allowed":false.*') -ne 0 ]
-  [ $(expr "$output" : '.*"message":".*images not allowed: ghcr.io/kubewarden/test-verify-image-signatures:signed.*') -ne 0 ]
+  [ $(expr "$output" : '.*"message":".*ghcr.io/kubewarden/test-verify-image-signatures:signed): image not allowed.*') -ne 0 ]
 }
 
 @test "Job: accept allowed image" {
@@ -80,3 +80,250 @@
   [ "$status" -eq 1 ]
   [ $(expr "$output" : '.*Provided settings are not valid.*invalid reference format.*') -ne 0 ]
 }
+
+@test "Pod: warn about deprecated registry" {
+  run kwctl run \
+    --request-path test_data/pod_creation_deprecated_registry.json \
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+  [ $(expr "$output" : '.*"warnings":\[".*deprecated registry k8s.gcr.io, use registry.k8s.io instead.*') -ne 0 ]
+}
+
+@test "Pod: reject deprecated registry" {
+  run kwctl run \
+    --request-path test_data/pod_creation_deprecated_registry.json \
+    --settings-json '{"deprecatedRegistries": {"action": "deny"}}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":false.*') -ne 0 ]
+  [ $(expr "$output" : '.*"message":".*deprecated registry k8s.gcr.io, use registry.k8s.io instead.*') -ne 0 ]
+}
+
+@test "Pod: only warn about not allowed tags" {
+  run kwctl run \
+    --request-path test_data/pod_creation_latest.json \
+    --settings-json '{"tags": {"reject": ["latest"], "action": "warn"}}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+  [ $(expr "$output" : '.*"warnings":\["container nginx (spec.containers\[0\].image: nginx:latest): tag latest not allowed".*') -ne 0 ]
+}
+
+@test "Pod: reject with a custom message and status code" {
+  run kwctl run \
+    --request-path test_data/pod_creation_latest.json \
+    --settings-json '{"tags": {"reject": ["latest"]}, "rejectionMessage": {"template": "{{image}} is not approved, see https://wiki.my-corp.com", "code": 403}}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":false.*') -ne 0 ]
+  [ $(expr "$output" : '.*"message":"nginx:latest is not approved, see https://wiki.my-corp.com".*') -ne 0 ]
+  [ $(expr "$output" : '.*"code":403.*') -ne 0 ]
+}
+
+@test "Pod: accept image allowed by a namespace overlay" {
+  run kwctl run \
+    --request-path test_data/pod_creation_sandbox_namespace.json \
+    --settings-json '{"registries": {"allow": ["registry.my-corp.com"]}, "overlays": [{"namespaces": ["sandbox-*"], "registries": {"allow": ["docker.io"]}}]}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+}
+
+@test "Pod: accept image from the registry path of its namespace" {
+  run kwctl run \
+    --request-path test_data/pod_creation_team_namespace.json \
+    --settings-json '{"repositories": {"registry.corp.com": {"namespaces": ["{{namespace}}"]}}}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+}
+
+@test "Deployment: accept exempted workload" {
+  run kwctl run \
+    --request-path test_data/deployment_creation_vendor_operator.json \
+    --settings-json '{"registries": {"reject": ["ghcr.io"]}, "exemptions": [{"name": "vendor-operator", "labelSelector": {"matchLabels": {"app.kubernetes.io/part-of": "vendor-operator"}}}]}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+}
+
+@test "Pod: accept request of exempted service account" {
+  run kwctl run \
+    --request-path test_data/pod_creation_gitops.json \
+    --settings-json '{"registries": {"reject": ["docker.io"]}, "exemptions": [{"name": "gitops", "serviceAccounts": ["argocd/*"]}]}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+}
+
+@test "Pod: break-glass downgrades rejections to warnings" {
+  run kwctl run \
+    --request-path test_data/pod_creation_break_glass.json \
+    --settings-json '{"registries": {"reject": ["docker.io"]}, "breakGlass": {"users": ["alice"]}}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+  [ $(expr "$output" : '.*"break-glass":"INC-1234 hotfix for the checkout outage".*') -ne 0 ]
+}
+
+@test "Pod: overlay selected by the kind of its Job" {
+  run kwctl run \
+    --request-path test_data/pod_creation_job.json \
+    --settings-json '{"registries": {"allow": ["registry.corp.com"]}, "overlays": [{"kinds": ["Job", "CronJob"], "registries": {"allow": ["registry.corp.com", "ghcr.io"]}}]}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+}
+
+@test "ReplicaSet: break-glass copied by the deployment controller" {
+  run kwctl run \
+    --request-path test_data/replicaset_creation_break_glass.json \
+    --settings-json '{"registries": {"reject": ["docker.io"]}, "breakGlass": {"users": ["alice"]}}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+  [ $(expr "$output" : '.*"break-glass":"INC-1234 hotfix for the checkout outage".*') -ne 0 ]
+}
+
+@test "Deployment: grandfather unchanged images on update" {
+  run kwctl run \
+    --request-path test_data/deployment_update.json \
+    --settings-json '{"registries": {"reject": ["ghcr.io"]}, "grandfatherExistingImages": true}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":false.*') -ne 0 ]
+  [ $(expr "$output" : '.*"message":".*container metrics-exporter.*') -ne 0 ]
+  [ $(expr "$output" : '.*"warnings":\[".*allowed until the image is changed.*') -ne 0 ]
+}
+
+@test "Pod: evaluate only the new ephemeral containers" {
+  run kwctl run \
+    --request-path test_data/pod_ephemeral_containers.json \
+    --settings-json '{"tags": {"reject": ["latest"]}, "containerTypes": {"ephemeralContainers": {"registries": {"allow": ["docker.io"]}}}}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+}
+
+@test "Pod: skip the Pods created by a ReplicaSet" {
+  run kwctl run \
+    --request-path test_data/pod_creation_controlled.json \
+    --settings-json '{"tags": {"reject": ["latest"]}, "skipControlledResources": true}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+  [ $(expr "$output" : '.*"skipped":"controlled by ReplicaSet nginx-7c5b9d8f6d".*') -ne 0 ]
+}
+
+@test "Pod: only warn about mirror Pods" {
+  run kwctl run \
+    --request-path test_data/pod_creation_mirror.json \
+    --settings-json '{"registries": {"reject": ["registry.k8s.io"]}, "mirrorPods": "warn"}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":true.*') -ne 0 ]
+  [ $(expr "$output" : '.*"warnings":\[".*registry registry.k8s.io not allowed.*') -ne 0 ]
+}
+
+@test "PodTemplate: reject not allowed registry" {
+  run kwctl run \
+    --request-path test_data/podtemplate_creation.json \
+    --settings-json '{"registries": {"reject": ["ghcr.io"]}}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":false.*') -ne 0 ]
+  [ $(expr "$output" : '.*"message":".*template.spec.containers\[0\].image: ghcr.io/kubewarden/test-verify-image-signatures:signed): registry ghcr.io not allowed.*') -ne 0 ]
+}
+
+@test "Pod: reject image volume from not allowed registry" {
+  run kwctl run \
+    --request-path test_data/pod_creation_image_volume.json \
+    --settings-json '{"registries": {"allow": ["ghcr.io"]}}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":false.*') -ne 0 ]
+  [ $(expr "$output" : '.*"message":".*image volume models (spec.volumes\[1\].image.reference: docker.io/my-org/models:v1): registry docker.io not allowed.*') -ne 0 ]
+}
+
+@test "Task: reject image found through the paths of a custom resource" {
+  run kwctl run \
+    --request-path test_data/task_creation.json \
+    --settings-json '{"registries": {"allow": ["ghcr.io"]}, "customResources": [{"group": "tekton.dev", "kind": "Task", "images": ["spec.steps[*].image"]}]}'\
+    annotated-policy.wasm
+
+  # this prints the output when one the checks below fails
+  echo "output = ${output}"
+
+  [ "$status" -eq 0 ]
+  [ $(expr "$output" : '.*"allowed":false.*') -ne 0 ]
+  [ $(expr "$output" : '.*"message":".*image push (spec.steps\[1\].image: docker.io/my-org/pusher:1.0.0): registry docker.io not allowed.*') -ne 0 ]
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kubewarden_policy_sdk::{request::UserInfo, response::ValidationResponse};
+
+use crate::{owners, settings::Subjects};
+
+/// Annotation used to request a break-glass, its value must be the ticket
+/// followed by the reason, e.g. `INC-1234 hotfix for the checkout outage`
+pub(crate) const BREAK_GLASS_ANNOTATION: &str = "trusted-repos.kubewarden.io/break-glass";
+
+/// Why the policy has been bypassed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Justification {
+    pub(crate) ticket: String,
+    pub(crate) reason: String,
+}
+
+impl Justification {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().split_once(char::is_whitespace) {
+            Some((ticket, reason)) if !reason.trim().is_empty() => Ok(Justification {
+                ticket: ticket.to_string(),
+                reason: reason.trim().to_string(),
+            }),
+            _ => Err(format!(
+                "{BREAK_GLASS_ANNOTATION} annotation ignored: its value must be a ticket followed by a reason"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Justification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.ticket, self.reason)
+    }
+}
+
+/// The justification of the break-glass requested through the annotations
+/// of the resource. The annotation is also honored on the resources created
+/// by the built-in controllers, which copy it from their parent, e.g. from a
+/// Deployment to its ReplicaSet. An error describes why the break-glass has
+/// been ignored, e.g. when the user is not allowed to request it
+pub(crate) fn requested(
+    metadata: &ObjectMeta,
+    allowed: &Subjects,
+    user_info: &UserInfo,
+) -> Result<Option<Justification>, String> {
+    let value = match metadata
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get(BREAK_GLASS_ANNOTATION))
+    {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    let from_controller = owners::validated_controller(metadata, user_info).is_some();
+    if allowed.is_empty() || !(allowed.matches(user_info) || from_controller) {
+        return Err(format!(
+            "{BREAK_GLASS_ANNOTATION} annotation ignored: user {} is not allowed to break glass",
+            user_info.username
+        ));
+    }
+    Justification::parse(value).map(Some)
+}
+
+/// Record the break-glass inside of the response: the justification and the
+/// bypassed violations are reported inside of the audit annotations
+pub(crate) fn record(
+    response: &mut ValidationResponse,
+    justification: &Justification,
+    bypassed: Vec<String>,
+) {
+    let audit_annotations = response.audit_annotations.get_or_insert_default();
+    audit_annotations.insert("break-glass".to_string(), justification.to_string());
+    if bypassed.is_empty() {
+        return;
+    }
+    audit_annotations.insert("break-glass.bypassed".to_string(), bypassed.join("; "));
+    response.warnings.get_or_insert_default().push(format!(
+        "break-glass {}: rejections downgraded to warnings",
+        justification.ticket
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::user_info;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+    use rstest::*;
+
+    fn metadata(break_glass: Option<&str>) -> ObjectMeta {
+        ObjectMeta {
+            owner_references: Some(vec![OwnerReference {
+                api_version: "apps/v1".to_string(),
+                kind: "Deployment".to_string(),
+                name: "nginx".to_string(),
+                uid: "7a3f1c2e-4b5d-4e6f-8a9b-0c1d2e3f4a5b".to_string(),
+                controller: Some(true),
+                ..Default::default()
+            }]),
+            annotations: break_glass.map(|value| {
+                [(BREAK_GLASS_ANNOTATION.to_string(), value.to_string())]
+                    .into_iter()
+                    .collect()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case::no_annotation(None, user_info("alice", &["sre"]), Ok(None))]
+    #[case::allowed_group(
+        Some("INC-1234 hotfix for the checkout outage"),
+        user_info("alice", &["sre"]),
+        Ok(Some(("INC-1234", "hotfix for the checkout outage")))
+    )]
+    #[case::allowed_user(
+        Some("  INC-1234   hotfix  "),
+        user_info("bob", &[]),
+        Ok(Some(("INC-1234", "hotfix")))
+    )]
+    #[case::user_not_allowed(
+        Some("INC-1234 hotfix"),
+        user_info("mallory", &["developers"]),
+        Err("user mallory is not allowed to break glass")
+    )]
+    #[case::created_by_controller(
+        Some("INC-1234 hotfix"),
+        user_info("system:serviceaccount:kube-system:deployment-controller", &[]),
+        Ok(Some(("INC-1234", "hotfix")))
+    )]
+    #[case::missing_reason(
+        Some("INC-1234"),
+        user_info("alice", &["sre"]),
+        Err("its value must be a ticket followed by a reason")
+    )]
+    #[case::empty_value(
+        Some(""),
+        user_info("alice", &["sre"]),
+        Err("its value must be a ticket followed by a reason")
+    )]
+    fn break_glass_requested(
+        #[case] break_glass: Option<&str>,
+        #[case] user_info: UserInfo,
+        #[case] expected: Result<Option<(&str, &str)>, &str>,
+    ) {
+        let allowed = Subjects {
+            users: vec!["bob".to_string()],
+            groups: vec!["sre".to_string()],
+            ..Default::default()
+        };
+
+        let result = requested(&metadata(break_glass), &allowed, &user_info);
+        match expected {
+            Ok(expected) => {
+                let expected = expected.map(|(ticket, reason)| Justification {
+                    ticket: ticket.to_string(),
+                    reason: reason.to_string(),
+                });
+                assert_eq!(result, Ok(expected));
+            }
+            Err(expected) => {
+                let error = result.expect_err("break-glass was supposed to be ignored");
+                assert!(error.ends_with(expected), "unexpected error {error}");
+            }
+        }
+    }
+
+    #[test]
+    fn break_glass_disabled_by_default() {
+        let result = requested(
+            &metadata(Some("INC-1234 hotfix")),
+            &Subjects::default(),
+            &user_info("alice", &["system:masters"]),
+        );
+        assert!(result.is_err(), "break-glass was supposed to be ignored");
+    }
+}
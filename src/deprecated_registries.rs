@@ -0,0 +1,153 @@
+use crate::settings::{Action, DeprecatedRegistries};
+
+/// Registries that have been frozen or shut down, together with their
+/// replacement. An entry can also refer to a repository namespace of a
+/// registry, like `gcr.io/google_containers`.
+///
+/// The table can be extended, or its entries overridden, through the
+/// `deprecatedRegistries` settings.
+pub(crate) const BUILTIN_DEPRECATED_REGISTRIES: &[(&str, &str)] = &[
+    ("k8s.gcr.io", "registry.k8s.io"),
+    ("gcr.io/google_containers", "registry.k8s.io"),
+    ("gcr.io/google-containers", "registry.k8s.io"),
+    ("us.gcr.io/k8s-artifacts-prod", "registry.k8s.io"),
+    ("eu.gcr.io/k8s-artifacts-prod", "registry.k8s.io"),
+    ("asia.gcr.io/k8s-artifacts-prod", "registry.k8s.io"),
+];
+
+/// A deprecated registry an image is pulled from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DeprecatedRegistryUsage {
+    /// The deprecated registry, as written inside of the table
+    pub(crate) registry: String,
+    pub(crate) replacement: String,
+    pub(crate) action: Action,
+}
+
+/// Look for the deprecated registry serving the given repository. When
+/// multiple entries match, the most specific one wins.
+pub(crate) fn find_deprecated_registry(
+    registry: &str,
+    repository: &str,
+    settings: &DeprecatedRegistries,
+) -> Option<DeprecatedRegistryUsage> {
+    let image_path = format!("{registry}/{repository}");
+
+    let user_defined = settings.registries.iter().map(|entry| {
+        (
+            entry.registry.as_str(),
+            entry.replacement.as_str(),
+            entry.action.unwrap_or(settings.action),
+        )
+    });
+    // entries provided by the user take precedence over the built-in ones
+    let builtin = BUILTIN_DEPRECATED_REGISTRIES
+        .iter()
+        .filter(|(prefix, _)| {
+            !settings
+                .registries
+                .iter()
+                .any(|entry| entry.registry == *prefix)
+        })
+        .map(|(prefix, replacement)| (*prefix, *replacement, settings.action));
+
+    user_defined
+        .chain(builtin)
+        .filter(|(prefix, _, _)| {
+            image_path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .max_by_key(|(prefix, _, _)| prefix.len())
+        .map(|(prefix, replacement, action)| DeprecatedRegistryUsage {
+            registry: prefix.to_string(),
+            replacement: replacement.to_string(),
+            action,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::settings::DeprecatedRegistry;
+
+    #[rstest]
+    #[case::not_deprecated("registry.k8s.io", "pause", Vec::new(), None)]
+    #[case::builtin_registry(
+        "k8s.gcr.io",
+        "pause",
+        Vec::new(),
+        Some(("k8s.gcr.io", "registry.k8s.io", Action::Warn))
+    )]
+    #[case::builtin_namespace(
+        "gcr.io",
+        "google_containers/pause",
+        Vec::new(),
+        Some(("gcr.io/google_containers", "registry.k8s.io", Action::Warn))
+    )]
+    #[case::same_registry_other_namespace("gcr.io", "my-project/app", Vec::new(), None)]
+    #[case::namespace_prefix_is_not_a_match(
+        "gcr.io",
+        "google_containers_fork/pause",
+        Vec::new(),
+        None
+    )]
+    #[case::builtin_entry_overridden(
+        "k8s.gcr.io",
+        "pause",
+        vec![DeprecatedRegistry {
+            registry: "k8s.gcr.io".to_string(),
+            replacement: "registry.corp.com/k8s".to_string(),
+            action: Some(Action::Deny),
+        }],
+        Some(("k8s.gcr.io", "registry.corp.com/k8s", Action::Deny))
+    )]
+    #[case::user_defined_entry(
+        "old-registry.corp.com",
+        "team/app",
+        vec![DeprecatedRegistry {
+            registry: "old-registry.corp.com".to_string(),
+            replacement: "registry.corp.com".to_string(),
+            action: None,
+        }],
+        Some(("old-registry.corp.com", "registry.corp.com", Action::Warn))
+    )]
+    #[case::most_specific_entry_wins(
+        "old-registry.corp.com",
+        "team/app",
+        vec![
+            DeprecatedRegistry {
+                registry: "old-registry.corp.com".to_string(),
+                replacement: "registry.corp.com".to_string(),
+                action: None,
+            },
+            DeprecatedRegistry {
+                registry: "old-registry.corp.com/team".to_string(),
+                replacement: "registry.corp.com/team".to_string(),
+                action: Some(Action::Deny),
+            },
+        ],
+        Some(("old-registry.corp.com/team", "registry.corp.com/team", Action::Deny))
+    )]
+    fn find_deprecated_registry_in_table(
+        #[case] registry: &str,
+        #[case] repository: &str,
+        #[case] user_defined: Vec<DeprecatedRegistry>,
+        #[case] expected: Option<(&str, &str, Action)>,
+    ) {
+        let settings = DeprecatedRegistries {
+            registries: user_defined,
+            ..DeprecatedRegistries::default()
+        };
+        let expected = expected.map(|(registry, replacement, action)| DeprecatedRegistryUsage {
+            registry: registry.to_string(),
+            replacement: replacement.to_string(),
+            action,
+        });
+
+        let found = find_deprecated_registry(registry, repository, &settings);
+        assert_eq!(found, expected, "got {found:?} instead of {expected:?}");
+    }
+}
@@ -0,0 +1,58 @@
+/// Match the value against a glob pattern: `*` matches any sequence of
+/// characters, `?` matches a single character. Patterns without wildcards
+/// must be equal to the value
+pub(crate) fn matches(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    // position of the last `*` inside of the pattern, together with the
+    // position of the value it has been matched against
+    let mut backtrack: Option<(usize, usize)> = None;
+    let (mut p, mut v) = (0, 0);
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, v));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` consume one more character
+                Some((star, star_v)) => {
+                    p = star + 1;
+                    v = star_v + 1;
+                    backtrack = Some((star, star_v + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::exact_match("default", "default", true)]
+    #[case::exact_mismatch("default", "default-2", false)]
+    #[case::prefix("sandbox-*", "sandbox-team-a", true)]
+    #[case::prefix_without_suffix("sandbox-*", "sandbox-", true)]
+    #[case::prefix_mismatch("sandbox-*", "prod-team-a", false)]
+    #[case::suffix("*-prod", "team-a-prod", true)]
+    #[case::infix("team-*-prod", "team-a-b-prod", true)]
+    #[case::infix_mismatch("team-*-prod", "team-a-staging", false)]
+    #[case::single_character("team-?", "team-a", true)]
+    #[case::single_character_mismatch("team-?", "team-ab", false)]
+    #[case::match_all("*", "kube-system", true)]
+    #[case::empty_value("*", "", true)]
+    fn glob_matches(#[case] pattern: &str, #[case] value: &str, #[case] expected: bool) {
+        assert_eq!(matches(pattern, value), expected);
+    }
+}
@@ -0,0 +1,217 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// A step of a path
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// A field of an object
+    Field(String),
+    /// An item of an array
+    Index(usize),
+    /// All the items of an array, written `[*]`
+    Wildcard,
+}
+
+/// A path inside of a JSON document, using the syntax of the Kubernetes field
+/// paths: fields separated by dots, followed by optional array indexes, e.g.
+/// `spec.steps[*].image` or `spec.templates[0].container.image`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        for part in path.split('.') {
+            let (field, mut indexes) = part.split_at(part.find('[').unwrap_or(part.len()));
+            if field.is_empty() {
+                return Err(format!("invalid path {path}: empty field name"));
+            }
+            segments.push(Segment::Field(field.to_string()));
+            while !indexes.is_empty() {
+                let (index, rest) = indexes
+                    .strip_prefix('[')
+                    .and_then(|indexes| indexes.split_once(']'))
+                    .ok_or_else(|| format!("invalid path {path}: malformed index of {field}"))?;
+                let segment = match index {
+                    "*" => Segment::Wildcard,
+                    index => Segment::Index(index.parse().map_err(|_| {
+                        format!("invalid path {path}: index {index} of {field} is not a number")
+                    })?),
+                };
+                segments.push(segment);
+                indexes = rest;
+            }
+        }
+        Ok(JsonPath { segments })
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (position, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Field(field) if position == 0 => write!(f, "{field}")?,
+                Segment::Field(field) => write!(f, ".{field}")?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+                Segment::Wildcard => write!(f, "[*]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonPath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        JsonPath::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for JsonPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl JsonPath {
+    /// The values found at the path, together with their own path where the
+    /// wildcards are replaced by the actual indexes, e.g.
+    /// `spec.steps[1].image`. Missing fields and items are skipped
+    pub(crate) fn select<'a>(&self, document: &'a Value) -> Vec<(String, &'a Value)> {
+        let mut selected = vec![(String::new(), document)];
+        for segment in &self.segments {
+            selected = selected
+                .into_iter()
+                .flat_map(|(path, value)| match segment {
+                    Segment::Field(field) => value
+                        .get(field)
+                        .map(|value| {
+                            let path = if path.is_empty() {
+                                field.clone()
+                            } else {
+                                format!("{path}.{field}")
+                            };
+                            (path, value)
+                        })
+                        .into_iter()
+                        .collect::<Vec<_>>(),
+                    Segment::Index(index) => value
+                        .get(index)
+                        .map(|value| (format!("{path}[{index}]"), value))
+                        .into_iter()
+                        .collect(),
+                    Segment::Wildcard => value
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .enumerate()
+                        .map(|(index, value)| (format!("{path}[{index}]"), value))
+                        .collect(),
+                })
+                .collect();
+        }
+        selected
+    }
+
+    /// The path of the object holding the last field, together with the
+    /// name of the field. `None` when the path ends with an index
+    pub(crate) fn split_last_field(&self) -> Option<(JsonPath, &str)> {
+        match self.segments.split_last() {
+            Some((Segment::Field(field), parent)) => Some((
+                JsonPath {
+                    segments: parent.to_vec(),
+                },
+                field,
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "spec": {
+                "builder": {"image": "golang:1.23"},
+                "steps": [
+                    {"name": "build", "image": "golang:1.23"},
+                    {"name": "script"},
+                    {"name": "push", "image": "gcr.io/kaniko-project/executor:v1.23.2"},
+                ],
+            }
+        })
+    }
+
+    #[rstest]
+    #[case::field("spec.builder.image", vec![("spec.builder.image", "golang:1.23")])]
+    #[case::index("spec.steps[2].image", vec![("spec.steps[2].image", "gcr.io/kaniko-project/executor:v1.23.2")])]
+    #[case::wildcard(
+        "spec.steps[*].image",
+        vec![
+            ("spec.steps[0].image", "golang:1.23"),
+            ("spec.steps[2].image", "gcr.io/kaniko-project/executor:v1.23.2"),
+        ]
+    )]
+    #[case::missing_field("spec.tasks[*].image", vec![])]
+    #[case::missing_index("spec.steps[5].image", vec![])]
+    fn select_values(#[case] path: &str, #[case] expected: Vec<(&str, &str)>) {
+        let path: JsonPath = path.parse().unwrap();
+        let document = document();
+
+        let selected: Vec<(String, &str)> = path
+            .select(&document)
+            .into_iter()
+            .map(|(path, value)| (path, value.as_str().unwrap()))
+            .collect();
+        let expected: Vec<(String, &str)> = expected
+            .into_iter()
+            .map(|(path, value)| (path.to_string(), value))
+            .collect();
+        assert_eq!(selected, expected);
+    }
+
+    #[rstest]
+    #[case::field("spec.builder.image", true)]
+    #[case::nested_indexes("spec.matrix[0][*].image", true)]
+    #[case::empty_path("", false)]
+    #[case::empty_field("spec..image", false)]
+    #[case::leading_index("[0].image", false)]
+    #[case::unclosed_index("spec.steps[*.image", false)]
+    #[case::invalid_index("spec.steps[first].image", false)]
+    fn parse_path(#[case] path: &str, #[case] is_valid: bool) {
+        let parsed = path.parse::<JsonPath>();
+        assert_eq!(parsed.is_ok(), is_valid, "unexpected result {parsed:?}");
+        if let Ok(parsed) = parsed {
+            assert_eq!(parsed.to_string(), path);
+        }
+    }
+
+    #[test]
+    fn split_last_field() {
+        let path: JsonPath = "spec.steps[*].image".parse().unwrap();
+        let (parent, field) = path.split_last_field().unwrap();
+        assert_eq!(parent.to_string(), "spec.steps[*]");
+        assert_eq!(field, "image");
+
+        let path: JsonPath = "spec.images[*]".parse().unwrap();
+        assert!(path.split_last_field().is_none());
+    }
+}
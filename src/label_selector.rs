@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, LabelSelectorRequirement};
+
+/// Whether the labels satisfy the selector, following the Kubernetes
+/// semantics: all the `matchLabels` and all the `matchExpressions` must be
+/// satisfied. An empty selector matches everything
+pub(crate) fn matches(selector: &LabelSelector, labels: &BTreeMap<String, String>) -> bool {
+    let labels_match = selector
+        .match_labels
+        .iter()
+        .flatten()
+        .all(|(key, value)| labels.get(key) == Some(value));
+    let expressions_match = selector
+        .match_expressions
+        .iter()
+        .flatten()
+        .all(|requirement| requirement_matches(requirement, labels));
+
+    labels_match && expressions_match
+}
+
+fn requirement_matches(
+    requirement: &LabelSelectorRequirement,
+    labels: &BTreeMap<String, String>,
+) -> bool {
+    let value = labels.get(&requirement.key);
+    let values = requirement.values.as_deref().unwrap_or_default();
+    match requirement.operator.as_str() {
+        "In" => value.is_some_and(|value| values.contains(value)),
+        "NotIn" => value.is_none_or(|value| !values.contains(value)),
+        "Exists" => value.is_some(),
+        "DoesNotExist" => value.is_none(),
+        // unknown operators are rejected by `validate`
+        _ => false,
+    }
+}
+
+/// Ensure the selector can be evaluated: known operators, values provided
+/// only to the operators that need them
+pub(crate) fn validate(selector: &LabelSelector) -> Result<(), String> {
+    let errors: Vec<String> = selector
+        .match_expressions
+        .iter()
+        .flatten()
+        .filter_map(|requirement| {
+            let has_values = requirement
+                .values
+                .as_ref()
+                .is_some_and(|values| !values.is_empty());
+            match requirement.operator.as_str() {
+                "In" | "NotIn" if !has_values => Some(format!(
+                    "{} operator of key {} requires values",
+                    requirement.operator, requirement.key
+                )),
+                "Exists" | "DoesNotExist" if has_values => Some(format!(
+                    "{} operator of key {} does not accept values",
+                    requirement.operator, requirement.key
+                )),
+                "In" | "NotIn" | "Exists" | "DoesNotExist" => None,
+                operator => Some(format!(
+                    "unknown operator {operator} for key {}",
+                    requirement.key
+                )),
+            }
+        })
+        .collect();
+
+    if !errors.is_empty() {
+        return Err(errors.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn labels() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("security-tier".to_string(), "restricted".to_string()),
+            ("team".to_string(), "payments".to_string()),
+        ])
+    }
+
+    #[rstest]
+    #[case::empty_selector(r#"{}"#, true)]
+    #[case::match_labels(r#"{"matchLabels": {"security-tier": "restricted"}}"#, true)]
+    #[case::match_labels_other_value(r#"{"matchLabels": {"security-tier": "baseline"}}"#, false)]
+    #[case::match_labels_missing_label(r#"{"matchLabels": {"env": "prod"}}"#, false)]
+    #[case::in_operator(
+        r#"{"matchExpressions": [{"key": "team", "operator": "In", "values": ["payments", "billing"]}]}"#,
+        true
+    )]
+    #[case::not_in_operator(
+        r#"{"matchExpressions": [{"key": "team", "operator": "NotIn", "values": ["payments"]}]}"#,
+        false
+    )]
+    #[case::not_in_operator_missing_label(
+        r#"{"matchExpressions": [{"key": "env", "operator": "NotIn", "values": ["prod"]}]}"#,
+        true
+    )]
+    #[case::exists_operator(
+        r#"{"matchExpressions": [{"key": "team", "operator": "Exists"}]}"#,
+        true
+    )]
+    #[case::does_not_exist_operator(
+        r#"{"matchExpressions": [{"key": "team", "operator": "DoesNotExist"}]}"#,
+        false
+    )]
+    #[case::labels_and_expressions(
+        r#"{"matchLabels": {"security-tier": "restricted"}, "matchExpressions": [{"key": "env", "operator": "Exists"}]}"#,
+        false
+    )]
+    fn selector_matches(#[case] selector: &str, #[case] expected: bool) {
+        let selector: LabelSelector = serde_json::from_str(selector).unwrap();
+        assert_eq!(matches(&selector, &labels()), expected);
+    }
+
+    #[rstest]
+    #[case::match_labels(r#"{"matchLabels": {"security-tier": "restricted"}}"#, true)]
+    #[case::in_operator(
+        r#"{"matchExpressions": [{"key": "team", "operator": "In", "values": ["payments"]}]}"#,
+        true
+    )]
+    #[case::in_operator_without_values(
+        r#"{"matchExpressions": [{"key": "team", "operator": "In"}]}"#,
+        false
+    )]
+    #[case::exists_operator_with_values(
+        r#"{"matchExpressions": [{"key": "team", "operator": "Exists", "values": ["payments"]}]}"#,
+        false
+    )]
+    #[case::unknown_operator(
+        r#"{"matchExpressions": [{"key": "team", "operator": "Equals", "values": ["payments"]}]}"#,
+        false
+    )]
+    fn validate_selector(#[case] selector: &str, #[case] is_valid: bool) {
+        let selector: LabelSelector = serde_json::from_str(selector).unwrap();
+        assert_eq!(validate(&selector).is_ok(), is_valid);
+    }
+}
@@ -1,27 +1,68 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Utc;
 use guest::prelude::*;
-use k8s_openapi::api::{
-    apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet},
-    batch::v1::{CronJob, Job},
-    core::v1::{Pod, ReplicationController},
+use k8s_openapi::{
+    api::{
+        apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet},
+        batch::v1::{CronJob, Job},
+        core::v1::{Namespace, Pod, PodTemplate, ReplicationController},
+    },
+    apimachinery::pkg::apis::meta::v1::ObjectMeta,
 };
+use kubewarden_policy_sdk::host_capabilities::kubernetes::GetResourceRequest;
+use kubewarden_policy_sdk::wapc_guest as guest;
 use kubewarden_policy_sdk::{
-    accept_request, logging, protocol_version_guest, request::ValidationRequest, validate_settings,
+    accept_request, logging, protocol_version_guest, reject_request, request::ValidationRequest,
+    response::ValidationResponse, validate_settings,
 };
-use kubewarden_policy_sdk::{response::ValidationResponse, wapc_guest as guest};
 use lazy_static::lazy_static;
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Deserialize};
 use slog::{o, warn, Logger};
 
+mod break_glass;
+
+mod deprecated_registries;
+
+mod glob;
+
+mod json_path;
+
+mod label_selector;
+
+mod owners;
+
+mod remediation;
+
+mod rule_set;
+
 mod validation_result;
 
 mod validation;
-use validation::validate_pod_spec;
+use validation::{
+    discover_custom_resource_images, discover_images, grandfather_existing_images, validate_images,
+    validate_new_ephemeral_containers, validate_pod_spec,
+};
+use validation_result::PodSpecValidationResult;
 
 mod validating_resource;
 use validating_resource::ValidatingResource;
 
 mod settings;
-use settings::Settings;
+use settings::{CustomResource, MirrorPods, RuleContext, Settings};
+
+mod template;
+
+#[cfg(test)]
+mod test_helpers;
+
+#[cfg(test)]
+use crate::tests::mock_kubernetes_sdk::get_resource;
+#[cfg(not(test))]
+use kubewarden_policy_sdk::host_capabilities::kubernetes::get_resource;
+
+/// Subresource used by `kubectl debug` to add ephemeral containers to a Pod
+const EPHEMERAL_CONTAINERS_SUBRESOURCE: &str = "ephemeralcontainers";
 
 lazy_static! {
     static ref LOG_DRAIN: Logger = Logger::root(
@@ -49,12 +90,19 @@ fn validate(payload: &[u8]) -> CallResult {
         "Job" => validate_resource::<Job>(validation_request),
         "CronJob" => validate_resource::<CronJob>(validation_request),
         "Pod" => validate_resource::<Pod>(validation_request),
-        _ => {
-            // We were forwarded a request we cannot unmarshal or
-            // understand, just accept it
-            warn!(LOG_DRAIN, "cannot unmarshal resource: this policy does not know how to evaluate this resource; accept it");
-            accept_request()
-        }
+        "PodTemplate" => validate_resource::<PodTemplate>(validation_request),
+        kind => match validation_request
+            .settings
+            .custom_resource(&validation_request.request.kind.group, kind)
+        {
+            Some(custom_resource) => validate_custom_resource(&validation_request, custom_resource),
+            None => {
+                // We were forwarded a request we cannot unmarshal or
+                // understand, just accept it
+                warn!(LOG_DRAIN, "cannot unmarshal resource: this policy does not know how to evaluate this resource; accept it");
+                accept_request()
+            }
+        },
     }
 }
 
@@ -72,6 +120,31 @@ fn validate_resource<T: ValidatingResource + DeserializeOwned>(
         }
     };
 
+    let metadata: Vec<&ObjectMeta> = std::iter::once(resource.metadata())
+        .chain(resource.template_metadata())
+        .collect();
+    let user_info = &validation_request.request.user_info;
+    if let Some(exemption) = validation_request.settings.exemption(&metadata, user_info) {
+        return accept_unevaluated("exemption", &exemption.name);
+    }
+
+    if validation_request.settings.skip_controlled_resources {
+        if let Some(controller) = owners::validated_controller(resource.metadata(), user_info) {
+            return accept_unevaluated(
+                "skipped",
+                &format!("controlled by {} {}", controller.kind, controller.name),
+            );
+        }
+    }
+    let mirror_pods = if owners::is_mirror_pod(resource.metadata(), user_info) {
+        validation_request.settings.mirror_pods
+    } else {
+        MirrorPods::Evaluate
+    };
+    if mirror_pods == MirrorPods::Skip {
+        return accept_unevaluated("skipped", "mirror pod");
+    }
+
     let spec = match resource.spec() {
         Some(spec) => spec,
         None => {
@@ -79,11 +152,178 @@ fn validate_resource<T: ValidatingResource + DeserializeOwned>(
         }
     };
 
-    let validation_response: ValidationResponse =
-        validate_pod_spec(&spec, &validation_request.settings).into();
+    let settings = match request_settings(&validation_request, resource.metadata()) {
+        Ok(settings) => settings,
+        Err(error) => return reject_request(Some(error), None, None, None),
+    };
+    let previous_spec = || {
+        serde_json::from_value::<T>(validation_request.request.old_object.clone())
+            .ok()
+            .and_then(|previous| previous.spec())
+    };
+    let mut validation_result =
+        if validation_request.request.sub_resource == EPHEMERAL_CONTAINERS_SUBRESOURCE {
+            // all the ephemeral containers are evaluated when the old object
+            // cannot be understood
+            validate_new_ephemeral_containers(
+                &spec,
+                &previous_spec().unwrap_or_default(),
+                T::SPEC_PATH,
+                &settings,
+                Utc::now().date_naive(),
+            )
+        } else {
+            validate_pod_spec(&spec, T::SPEC_PATH, &settings, Utc::now().date_naive())
+        };
+    if settings.grandfather_existing_images && validation_request.request.operation == "UPDATE" {
+        // all the images are evaluated when the old object cannot be
+        // understood
+        if let Some(previous_spec) = previous_spec() {
+            let previous_images = discover_images(&previous_spec, T::SPEC_PATH);
+            grandfather_existing_images(&mut validation_result, &previous_images);
+        }
+    }
+    if mirror_pods == MirrorPods::Warn {
+        // the kubelet keeps running the static Pods, rejecting their mirror
+        // only hides them from the API server
+        validation_result.rejection_reasons.bypass_denials();
+    }
+    respond(
+        &validation_request,
+        &settings,
+        resource.metadata(),
+        validation_result,
+    )
+}
+
+// validate the resources the policy does not know about, through the paths
+// of their images provided by the settings
+fn validate_custom_resource(
+    validation_request: &ValidationRequest<Settings>,
+    custom_resource: &CustomResource,
+) -> CallResult {
+    let object = &validation_request.request.object;
+    let metadata = object
+        .get("metadata")
+        .and_then(|metadata| ObjectMeta::deserialize(metadata).ok())
+        .unwrap_or_default();
+    let user_info = &validation_request.request.user_info;
+    if let Some(exemption) = validation_request
+        .settings
+        .exemption(&[&metadata], user_info)
+    {
+        return accept_unevaluated("exemption", &exemption.name);
+    }
+
+    let settings = match request_settings(validation_request, &metadata) {
+        Ok(settings) => settings,
+        Err(error) => return reject_request(Some(error), None, None, None),
+    };
+    let images = discover_custom_resource_images(object, custom_resource);
+    let mut validation_result = validate_images(&images, &settings, Utc::now().date_naive());
+    if settings.grandfather_existing_images && validation_request.request.operation == "UPDATE" {
+        let previous_images = discover_custom_resource_images(
+            &validation_request.request.old_object,
+            custom_resource,
+        );
+        grandfather_existing_images(&mut validation_result, &previous_images);
+    }
+    respond(validation_request, &settings, &metadata, validation_result)
+}
+
+/// The settings to be used for the request: the overlays selecting it are
+/// applied, then the placeholders of the rules are filled in. An error
+/// describes why the Namespace of the request cannot be fetched
+fn request_settings(
+    validation_request: &ValidationRequest<Settings>,
+    metadata: &ObjectMeta,
+) -> Result<Settings, String> {
+    let namespace = &validation_request.request.namespace;
+    // the Namespace object is fetched only when some overlay or placeholder
+    // needs its labels, cluster-scoped resources have no namespace to look for
+    let namespace_labels =
+        if validation_request.settings.needs_namespace_labels() && !namespace.is_empty() {
+            fetch_namespace_labels(namespace)
+                .map_err(|error| format!("cannot fetch namespace {namespace}: {error}"))?
+        } else {
+            BTreeMap::new()
+        };
+    // the resources created by a controller, like the Pods of a Job, are
+    // also selected by the kind of their controller
+    let user_info = &validation_request.request.user_info;
+    let kinds: Vec<&str> = std::iter::once(validation_request.request.kind.kind.as_str())
+        .chain(owners::validated_controller(metadata, user_info).map(|owner| owner.kind.as_str()))
+        .collect();
+    let labels = metadata.labels.clone().unwrap_or_default();
+    Ok(validation_request
+        .settings
+        .overlaid(&kinds, namespace, &namespace_labels, user_info)
+        .for_request(&RuleContext {
+            namespace,
+            labels: &labels,
+            namespace_labels: &namespace_labels,
+        }))
+}
+
+/// Build the response of an evaluated request. The rejections are downgraded
+/// to warnings when a break-glass is requested through the metadata of the
+/// resource
+fn respond(
+    validation_request: &ValidationRequest<Settings>,
+    settings: &Settings,
+    metadata: &ObjectMeta,
+    mut validation_result: PodSpecValidationResult,
+) -> CallResult {
+    let break_glass = break_glass::requested(
+        metadata,
+        &settings.break_glass,
+        &validation_request.request.user_info,
+    );
+    let bypassed = match &break_glass {
+        Ok(Some(_)) => validation_result.rejection_reasons.bypass_denials(),
+        _ => Vec::new(),
+    };
+    let mut validation_response = validation_result.into_response(&settings.rejection_message);
+    match break_glass {
+        Ok(Some(justification)) => {
+            break_glass::record(&mut validation_response, &justification, bypassed)
+        }
+        Ok(None) => {}
+        Err(warning) => validation_response
+            .warnings
+            .get_or_insert_default()
+            .push(warning),
+    }
+    Ok(serde_json::to_vec(&validation_response)?)
+}
+
+/// Accept the request without evaluating it, recording why inside of the
+/// given audit annotation
+fn accept_unevaluated(annotation: &str, value: &str) -> CallResult {
+    let validation_response = ValidationResponse {
+        accepted: true,
+        message: None,
+        code: None,
+        mutated_object: None,
+        audit_annotations: Some(HashMap::from([(annotation.to_string(), value.to_string())])),
+        warnings: None,
+    };
     Ok(serde_json::to_vec(&validation_response)?)
 }
 
+/// Fetch the labels of the given namespace through the Kubernetes host
+/// capabilities
+fn fetch_namespace_labels(namespace: &str) -> anyhow::Result<BTreeMap<String, String>> {
+    let namespace: Namespace = get_resource(&GetResourceRequest {
+        api_version: "v1".to_string(),
+        kind: "Namespace".to_string(),
+        name: namespace.to_string(),
+        namespace: None,
+        disable_cache: false,
+    })?;
+    Ok(namespace.metadata.labels.unwrap_or_default())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,7 +331,19 @@ mod tests {
     use crate::settings::Registries;
 
     use kubewarden_policy_sdk::test::Testcase;
+    use mockall::automock;
     use rstest::*;
+    use serial_test::serial;
+
+    #[automock]
+    pub mod kubernetes_sdk {
+        use kubewarden_policy_sdk::host_capabilities::kubernetes::GetResourceRequest;
+
+        #[allow(dead_code)]
+        pub fn get_resource<T: 'static>(_req: &GetResourceRequest) -> anyhow::Result<T> {
+            Err(anyhow::anyhow!("not mocked"))
+        }
+    }
 
     #[rstest]
     // Note: this test cares only about covering the switch statement of the resournce kind
@@ -103,6 +355,7 @@ mod tests {
     #[case::job("test_data/job_creation.json", false)]
     #[case::cronjob("test_data/cronjob_creation.json", false)]
     #[case::pod("test_data/pod_creation.json", false)]
+    #[case::podtemplate("test_data/podtemplate_creation.json", false)]
     #[case::ingress("test_data/ingress_creation.json", true)]
     fn test_validate(#[case] fixture: &str, #[case] expected_validation_result: bool) {
         let settings = Settings {
@@ -125,4 +378,633 @@ mod tests {
 
         assert!(test_case.eval(validate).is_ok());
     }
+
+    #[rstest]
+    #[case::pod(
+        "test_data/pod_creation.json",
+        "container nginx (spec.containers[0].image: nginx:1.0.0)"
+    )]
+    #[case::deployment(
+        "test_data/deployment_creation.json",
+        "container test-verify-image-signatures (spec.template.spec.containers[0].image: ghcr.io/kubewarden/test-verify-image-signatures:signed)"
+    )]
+    #[case::cronjob(
+        "test_data/cronjob_creation.json",
+        "container test-verify-image-signatures (spec.jobTemplate.spec.template.spec.containers[0].image: ghcr.io/kubewarden/test-verify-image-signatures:signed)"
+    )]
+    fn rejection_message_reports_the_container(
+        #[case] fixture: &str,
+        #[case] expected_container: &str,
+    ) {
+        let settings = Settings {
+            registries: Registries {
+                reject: vec!["ghcr.io".to_string(), "docker.io".to_string()]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+
+            ..Default::default()
+        };
+
+        let test_case = Testcase {
+            name: "rejection_message_reports_the_container".to_string(),
+            fixture_file: fixture.to_string(),
+            settings,
+            expected_validation_result: false,
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        let message = response.message.expect("rejection message not found");
+        assert!(
+            message.contains(expected_container),
+            "container {expected_container} not found inside of {message}"
+        );
+    }
+
+    #[rstest]
+    #[case::namespace_without_overlay("test_data/pod_creation.json", false)]
+    #[case::namespace_with_overlay("test_data/pod_creation_sandbox_namespace.json", true)]
+    fn overlays_selected_by_namespace(
+        #[case] fixture: &str,
+        #[case] expected_validation_result: bool,
+    ) {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com"]},
+                "overlays": [
+                    {"namespaces": ["sandbox-*"], "registries": {"allow": ["registry.corp.com", "docker.io"]}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let test_case = Testcase {
+            name: "overlays_selected_by_namespace".to_string(),
+            fixture_file: fixture.to_string(),
+            settings,
+            expected_validation_result,
+        };
+
+        assert!(test_case.eval(validate).is_ok());
+    }
+
+    #[rstest]
+    #[case::job("test_data/job_creation.json", true)]
+    #[case::cronjob("test_data/cronjob_creation.json", true)]
+    #[case::pod_of_a_job("test_data/pod_creation_job.json", true)]
+    #[case::deployment("test_data/deployment_creation.json", false)]
+    #[case::statefulset("test_data/statefulset_creation.json", false)]
+    fn overlays_selected_by_kind(#[case] fixture: &str, #[case] expected_validation_result: bool) {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com"]},
+                "overlays": [
+                    {"kinds": ["Job", "CronJob"], "registries": {"allow": ["registry.corp.com", "ghcr.io"]}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let test_case = Testcase {
+            name: "overlays_selected_by_kind".to_string(),
+            fixture_file: fixture.to_string(),
+            settings,
+            expected_validation_result,
+        };
+
+        assert!(test_case.eval(validate).is_ok());
+    }
+
+    #[rstest]
+    #[case::not_configured(r#"{"registries": {"allow": ["ghcr.io"]}}"#, None)]
+    #[case::other_group(
+        r#"{"registries": {"allow": ["ghcr.io"]}, "customResources": [{"group": "example.com", "kind": "Task", "images": ["spec.steps[*].image"]}]}"#,
+        None
+    )]
+    #[case::image_paths(
+        r#"{"registries": {"allow": ["ghcr.io"]}, "customResources": [{"group": "tekton.dev", "kind": "Task", "images": ["spec.steps[*].image"]}]}"#,
+        Some("not allowed, reported errors: image push (spec.steps[1].image: docker.io/my-org/pusher:1.0.0): registry docker.io not allowed")
+    )]
+    fn custom_resources_evaluated(#[case] settings: &str, #[case] expected_message: Option<&str>) {
+        let test_case = Testcase {
+            name: "custom_resources_evaluated".to_string(),
+            fixture_file: "test_data/task_creation.json".to_string(),
+            settings: serde_json::from_str::<Settings>(settings).unwrap(),
+            expected_validation_result: expected_message.is_none(),
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        assert_eq!(response.message.as_deref(), expected_message);
+    }
+
+    #[test]
+    fn image_volumes_evaluated() {
+        let settings = Settings {
+            registries: Registries {
+                allow: vec!["ghcr.io".to_string()].into_iter().collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let test_case = Testcase {
+            name: "image_volumes_evaluated".to_string(),
+            fixture_file: "test_data/pod_creation_image_volume.json".to_string(),
+            settings,
+            expected_validation_result: false,
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        assert_eq!(
+            response.message.as_deref(),
+            Some("not allowed, reported errors: image volume models (spec.volumes[1].image.reference: docker.io/my-org/models:v1): registry docker.io not allowed")
+        );
+    }
+
+    #[rstest]
+    #[case::controlled_evaluated(
+        "test_data/pod_creation_controlled.json",
+        r#"{"tags": {"reject": ["latest"]}}"#,
+        false,
+        None
+    )]
+    #[case::controlled_skipped(
+        "test_data/pod_creation_controlled.json",
+        r#"{"tags": {"reject": ["latest"]}, "skipControlledResources": true}"#,
+        true,
+        Some("controlled by ReplicaSet nginx-7c5b9d8f6d")
+    )]
+    #[case::mirror_pod_evaluated(
+        "test_data/pod_creation_mirror.json",
+        r#"{"registries": {"reject": ["registry.k8s.io"]}}"#,
+        false,
+        None
+    )]
+    #[case::mirror_pod_warned(
+        "test_data/pod_creation_mirror.json",
+        r#"{"registries": {"reject": ["registry.k8s.io"]}, "mirrorPods": "warn"}"#,
+        true,
+        None
+    )]
+    #[case::mirror_pod_skipped(
+        "test_data/pod_creation_mirror.json",
+        r#"{"registries": {"reject": ["registry.k8s.io"]}, "mirrorPods": "skip"}"#,
+        true,
+        Some("mirror pod")
+    )]
+    fn controlled_resources_and_mirror_pods(
+        #[case] fixture_file: &str,
+        #[case] settings: &str,
+        #[case] expected_validation_result: bool,
+        #[case] expected_skipped: Option<&str>,
+    ) {
+        let test_case = Testcase {
+            name: "controlled_resources_and_mirror_pods".to_string(),
+            fixture_file: fixture_file.to_string(),
+            settings: serde_json::from_str::<Settings>(settings).unwrap(),
+            expected_validation_result,
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        let skipped = response
+            .audit_annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get("skipped"));
+        assert_eq!(skipped.map(String::as_str), expected_skipped);
+    }
+
+    #[rstest]
+    #[case::new_debug_container(
+        r#"{"registries": {"allow": ["registry.my-corp.com"]}}"#,
+        Some("not allowed, reported errors: ephemeral container debugger-2 (spec.ephemeralContainers[1].image: docker.io/nicolaka/netshoot:v0.13): registry docker.io not allowed")
+    )]
+    #[case::debug_images_allowed(
+        r#"{"registries": {"allow": ["registry.my-corp.com"]}, "containerTypes": {"ephemeralContainers": {"registries": {"allow": ["docker.io"]}}}}"#,
+        None
+    )]
+    fn only_new_ephemeral_containers_evaluated(
+        #[case] settings: &str,
+        #[case] expected_message: Option<&str>,
+    ) {
+        let test_case = Testcase {
+            name: "only_new_ephemeral_containers_evaluated".to_string(),
+            fixture_file: "test_data/pod_ephemeral_containers.json".to_string(),
+            settings: serde_json::from_str::<Settings>(settings).unwrap(),
+            expected_validation_result: expected_message.is_none(),
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        assert_eq!(response.message.as_deref(), expected_message);
+    }
+
+    #[rstest]
+    #[case::grandfathered(
+        true,
+        vec!["container metrics-exporter (spec.template.spec.containers[1].image: ghcr.io/kubewarden/metrics-exporter:1.0.0): registry ghcr.io not allowed"],
+        vec!["container test-verify-image-signatures (spec.template.spec.containers[0].image: ghcr.io/kubewarden/test-verify-image-signatures:signed): registry ghcr.io not allowed, allowed until the image is changed"]
+    )]
+    #[case::not_grandfathered(
+        false,
+        vec![
+            "container test-verify-image-signatures (spec.template.spec.containers[0].image: ghcr.io/kubewarden/test-verify-image-signatures:signed): registry ghcr.io not allowed",
+            "container metrics-exporter (spec.template.spec.containers[1].image: ghcr.io/kubewarden/metrics-exporter:1.0.0): registry ghcr.io not allowed",
+        ],
+        Vec::new()
+    )]
+    fn existing_images_grandfathered_on_update(
+        #[case] grandfather_existing_images: bool,
+        #[case] expected_errors: Vec<&str>,
+        #[case] expected_warnings: Vec<&str>,
+    ) {
+        let settings = Settings {
+            registries: Registries {
+                reject: vec!["ghcr.io".to_string()].into_iter().collect(),
+                ..Default::default()
+            },
+            grandfather_existing_images,
+            ..Default::default()
+        };
+
+        let test_case = Testcase {
+            name: "existing_images_grandfathered_on_update".to_string(),
+            fixture_file: "test_data/deployment_update.json".to_string(),
+            settings,
+            expected_validation_result: false,
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        assert_eq!(
+            response.message,
+            Some(format!(
+                "not allowed, reported errors: {}",
+                expected_errors.join("; ")
+            ))
+        );
+        let expected_warnings: Vec<String> =
+            expected_warnings.into_iter().map(String::from).collect();
+        assert_eq!(response.warnings.unwrap_or_default(), expected_warnings);
+    }
+
+    #[rstest]
+    #[case::namespace_placeholder(r#"["{{namespace}}"]"#, true)]
+    #[case::label_placeholder(r#"["{{labels.team}}"]"#, false)]
+    #[case::missing_label(r#"["{{labels.owner}}"]"#, false)]
+    fn templated_rules_filled_from_the_request(
+        #[case] namespaces: &str,
+        #[case] expected_validation_result: bool,
+    ) {
+        let settings: Settings = serde_json::from_str(&format!(
+            r#"{{"repositories": {{"registry.corp.com": {{"namespaces": {namespaces}}}}}}}"#
+        ))
+        .unwrap();
+
+        let test_case = Testcase {
+            name: "templated_rules_filled_from_the_request".to_string(),
+            fixture_file: "test_data/pod_creation_team_namespace.json".to_string(),
+            settings,
+            expected_validation_result,
+        };
+
+        assert!(test_case.eval(validate).is_ok());
+    }
+
+    #[rstest]
+    #[case::labels_of_the_resource(
+        r#"{"name": "vendor-operator", "labelSelector": {"matchLabels": {"app.kubernetes.io/part-of": "vendor-operator"}}}"#,
+        Some("vendor-operator")
+    )]
+    #[case::annotations_of_the_pod_template(
+        r#"{"name": "vendor-managed", "annotationSelector": {"matchLabels": {"vendor.example.com/managed": "true"}}}"#,
+        Some("vendor-managed")
+    )]
+    #[case::not_exempted(
+        r#"{"name": "other-vendor", "labelSelector": {"matchLabels": {"app.kubernetes.io/part-of": "other-vendor"}}}"#,
+        None
+    )]
+    fn exempted_workloads(#[case] exemption: &str, #[case] expected_exemption: Option<&str>) {
+        let settings: Settings = serde_json::from_str(&format!(
+            r#"{{"registries": {{"reject": ["ghcr.io"]}}, "exemptions": [{exemption}]}}"#
+        ))
+        .unwrap();
+
+        let test_case = Testcase {
+            name: "exempted_workloads".to_string(),
+            fixture_file: "test_data/deployment_creation_vendor_operator.json".to_string(),
+            settings,
+            expected_validation_result: expected_exemption.is_some(),
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        let exemption = response
+            .audit_annotations
+            .unwrap_or_default()
+            .get("exemption")
+            .cloned();
+        assert_eq!(exemption.as_deref(), expected_exemption);
+    }
+
+    #[rstest]
+    #[case::gitops_controller_exempted(
+        "test_data/pod_creation_gitops.json",
+        r#"{"name": "gitops", "serviceAccounts": ["argocd/*"]}"#,
+        Some("gitops")
+    )]
+    #[case::developer_not_exempted(
+        "test_data/pod_creation.json",
+        r#"{"name": "gitops", "serviceAccounts": ["argocd/*"]}"#,
+        None
+    )]
+    #[case::workload_and_user_must_match(
+        "test_data/pod_creation_gitops.json",
+        r#"{"name": "gitops-vendor", "serviceAccounts": ["argocd/*"], "labelSelector": {"matchLabels": {"app.kubernetes.io/part-of": "vendor-operator"}}}"#,
+        None
+    )]
+    fn exempted_users(
+        #[case] fixture: &str,
+        #[case] exemption: &str,
+        #[case] expected_exemption: Option<&str>,
+    ) {
+        let settings: Settings = serde_json::from_str(&format!(
+            r#"{{"registries": {{"reject": ["docker.io"]}}, "exemptions": [{exemption}]}}"#
+        ))
+        .unwrap();
+
+        let test_case = Testcase {
+            name: "exempted_users".to_string(),
+            fixture_file: fixture.to_string(),
+            settings,
+            expected_validation_result: expected_exemption.is_some(),
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        let exemption = response
+            .audit_annotations
+            .unwrap_or_default()
+            .get("exemption")
+            .cloned();
+        assert_eq!(exemption.as_deref(), expected_exemption);
+    }
+
+    #[rstest]
+    #[case::deployment_exempted("test_data/deployment_creation_gitops.json", false, Some(("exemption", "gitops")))]
+    #[case::replica_set_evaluated("test_data/replicaset_creation_controlled.json", false, None)]
+    #[case::replica_set_skipped(
+        "test_data/replicaset_creation_controlled.json",
+        true,
+        Some(("skipped", "controlled by Deployment nginx"))
+    )]
+    fn exempted_users_and_controlled_resources(
+        #[case] fixture: &str,
+        #[case] skip_controlled_resources: bool,
+        #[case] expected_annotation: Option<(&str, &str)>,
+    ) {
+        let settings: Settings = serde_json::from_str(&format!(
+            r#"{{
+                "registries": {{"reject": ["docker.io"]}},
+                "exemptions": [{{"name": "gitops", "serviceAccounts": ["argocd/*"]}}],
+                "skipControlledResources": {skip_controlled_resources}
+            }}"#
+        ))
+        .unwrap();
+
+        let test_case = Testcase {
+            name: "exempted_users_and_controlled_resources".to_string(),
+            fixture_file: fixture.to_string(),
+            settings,
+            expected_validation_result: expected_annotation.is_some(),
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        let audit_annotations = response.audit_annotations.unwrap_or_default();
+        if let Some((key, value)) = expected_annotation {
+            assert_eq!(audit_annotations.get(key).map(String::as_str), Some(value));
+        }
+    }
+
+    #[test]
+    fn break_glass_downgrades_rejections() {
+        let settings: Settings = serde_json::from_str(
+            r#"{"registries": {"reject": ["docker.io"]}, "breakGlass": {"users": ["alice"]}}"#,
+        )
+        .unwrap();
+
+        let test_case = Testcase {
+            name: "break_glass_downgrades_rejections".to_string(),
+            fixture_file: "test_data/pod_creation_break_glass.json".to_string(),
+            settings,
+            expected_validation_result: true,
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        let audit_annotations = response
+            .audit_annotations
+            .expect("audit annotations not found");
+        assert_eq!(
+            audit_annotations.get("break-glass").map(String::as_str),
+            Some("INC-1234 hotfix for the checkout outage")
+        );
+        assert_eq!(
+            audit_annotations.get("break-glass.bypassed").map(String::as_str),
+            Some("container nginx (spec.containers[0].image: nginx:1.0.0): registry docker.io not allowed")
+        );
+        assert_eq!(
+            response.warnings,
+            Some(vec![
+                "container nginx (spec.containers[0].image: nginx:1.0.0): registry docker.io not allowed".to_string(),
+                "break-glass INC-1234: rejections downgraded to warnings".to_string(),
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case::deployment("test_data/deployment_creation_break_glass.json")]
+    #[case::replica_set("test_data/replicaset_creation_break_glass.json")]
+    #[case::pod("test_data/pod_creation_break_glass_controlled.json")]
+    fn break_glass_propagated_to_controlled_resources(#[case] fixture_file: &str) {
+        let settings: Settings = serde_json::from_str(
+            r#"{"registries": {"reject": ["docker.io"]}, "breakGlass": {"users": ["alice"]}}"#,
+        )
+        .unwrap();
+
+        let test_case = Testcase {
+            name: "break_glass_propagated_to_controlled_resources".to_string(),
+            fixture_file: fixture_file.to_string(),
+            settings,
+            expected_validation_result: true,
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        let audit_annotations = response
+            .audit_annotations
+            .expect("audit annotations not found");
+        assert_eq!(
+            audit_annotations.get("break-glass").map(String::as_str),
+            Some("INC-1234 hotfix for the checkout outage")
+        );
+    }
+
+    #[test]
+    fn break_glass_ignored_for_other_users() {
+        let settings: Settings = serde_json::from_str(
+            r#"{"registries": {"reject": ["docker.io"]}, "breakGlass": {"groups": ["sre"]}}"#,
+        )
+        .unwrap();
+
+        let test_case = Testcase {
+            name: "break_glass_ignored_for_other_users".to_string(),
+            fixture_file: "test_data/pod_creation_break_glass.json".to_string(),
+            settings,
+            expected_validation_result: false,
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        assert_eq!(
+            response.warnings,
+            Some(vec![
+                "trusted-repos.kubewarden.io/break-glass annotation ignored: user alice is not allowed to break glass".to_string()
+            ])
+        );
+        assert!(!response
+            .audit_annotations
+            .unwrap_or_default()
+            .contains_key("break-glass"));
+    }
+
+    fn namespace(name: &str, labels: &[(&str, &str)]) -> Namespace {
+        Namespace {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                labels: Some(
+                    labels
+                        .iter()
+                        .map(|(key, value)| (key.to_string(), value.to_string()))
+                        .collect(),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case::restricted_namespace(&[("security-tier", "restricted")], false)]
+    #[case::other_namespace(&[("security-tier", "baseline")], true)]
+    #[case::namespace_without_labels(&[], true)]
+    #[serial]
+    fn overlays_selected_by_namespace_labels(
+        #[case] labels: &'static [(&'static str, &'static str)],
+        #[case] expected_validation_result: bool,
+    ) {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "overlays": [
+                    {"namespaceSelector": {"matchLabels": {"security-tier": "restricted"}}, "registries": {"allow": ["registry.corp.com"]}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let ctx = mock_kubernetes_sdk::get_resource_context();
+        ctx.expect::<Namespace>()
+            .times(1)
+            .withf(|request| {
+                request.kind == "Namespace"
+                    && request.name == "sandbox-team-a"
+                    && request.namespace.is_none()
+            })
+            .returning(move |_| Ok(namespace("sandbox-team-a", labels)));
+
+        let test_case = Testcase {
+            name: "overlays_selected_by_namespace_labels".to_string(),
+            fixture_file: "test_data/pod_creation_sandbox_namespace.json".to_string(),
+            settings,
+            expected_validation_result,
+        };
+
+        assert!(test_case.eval(validate).is_ok());
+    }
+
+    #[rstest]
+    #[case::namespace_of_the_team(&[("team", "team-a")], true)]
+    #[case::namespace_of_another_team(&[("team", "checkout")], false)]
+    #[case::namespace_without_labels(&[], false)]
+    #[serial]
+    fn rules_filled_from_namespace_labels(
+        #[case] labels: &'static [(&'static str, &'static str)],
+        #[case] expected_validation_result: bool,
+    ) {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com"]},
+                "repositories": {"registry.corp.com": {"namespaces": ["{{namespaceLabels.team}}"]}}
+            }"#,
+        )
+        .unwrap();
+
+        let ctx = mock_kubernetes_sdk::get_resource_context();
+        ctx.expect::<Namespace>()
+            .times(1)
+            .withf(|request| request.kind == "Namespace" && request.name == "team-a")
+            .returning(move |_| Ok(namespace("team-a", labels)));
+
+        let test_case = Testcase {
+            name: "rules_filled_from_namespace_labels".to_string(),
+            fixture_file: "test_data/pod_creation_team_namespace.json".to_string(),
+            settings,
+            expected_validation_result,
+        };
+
+        assert!(test_case.eval(validate).is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn namespace_not_fetched_without_selectors() {
+        let ctx = mock_kubernetes_sdk::get_resource_context();
+        ctx.expect::<Namespace>().never();
+
+        let test_case = Testcase {
+            name: "namespace_not_fetched_without_selectors".to_string(),
+            fixture_file: "test_data/pod_creation_sandbox_namespace.json".to_string(),
+            settings: Settings::default(),
+            expected_validation_result: true,
+        };
+
+        assert!(test_case.eval(validate).is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn reject_when_namespace_cannot_be_fetched() {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "overlays": [
+                    {"namespaceSelector": {"matchLabels": {"security-tier": "restricted"}}, "registries": {"allow": ["registry.corp.com"]}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let ctx = mock_kubernetes_sdk::get_resource_context();
+        ctx.expect::<Namespace>().times(1).returning(|_| {
+            Err(anyhow::anyhow!(
+                "namespaces \"sandbox-team-a\" is forbidden"
+            ))
+        });
+
+        let test_case = Testcase {
+            name: "reject_when_namespace_cannot_be_fetched".to_string(),
+            fixture_file: "test_data/pod_creation_sandbox_namespace.json".to_string(),
+            settings,
+            expected_validation_result: false,
+        };
+
+        let response = test_case.eval(validate).unwrap();
+        let message = response.message.expect("rejection message not found");
+        assert!(
+            message.starts_with("cannot fetch namespace sandbox-team-a"),
+            "unexpected message {message}"
+        );
+    }
 }
@@ -0,0 +1,164 @@
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
+use kubewarden_policy_sdk::request::UserInfo;
+
+use crate::settings::Subjects;
+
+/// Annotation set by the kubelet on the mirror Pods of the static Pods
+pub(crate) const MIRROR_POD_ANNOTATION: &str = "kubernetes.io/config.mirror";
+
+/// The controllers validated by the policy, as `(apiVersion, kind)`
+const VALIDATED_CONTROLLERS: &[(&str, &str)] = &[
+    ("apps/v1", "Deployment"),
+    ("apps/v1", "ReplicaSet"),
+    ("apps/v1", "StatefulSet"),
+    ("apps/v1", "DaemonSet"),
+    ("v1", "ReplicationController"),
+    ("batch/v1", "Job"),
+    ("batch/v1", "CronJob"),
+];
+
+/// The service accounts of the built-in controllers creating the resources
+/// validated by the policy, inside of the `kube-system` namespace
+const CONTROLLER_SERVICE_ACCOUNTS: &[&str] = &[
+    "replicaset-controller",
+    "deployment-controller",
+    "job-controller",
+    "cronjob-controller",
+    "statefulset-controller",
+    "daemon-set-controller",
+    "replication-controller",
+];
+
+/// The users the built-in controllers make their requests as: the controller
+/// manager, either with its own credentials or with the service accounts of
+/// each controller. The other service accounts of `kube-system`, like the
+/// ones of the CNI or CSI add-ons, are not trusted
+pub(crate) fn controller_manager() -> Subjects {
+    Subjects {
+        users: vec!["system:kube-controller-manager".to_string()],
+        service_accounts: CONTROLLER_SERVICE_ACCOUNTS
+            .iter()
+            .map(|name| format!("kube-system/{name}"))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// The controller of the resource, when it is a kind validated by the policy
+/// and the request comes from the controller manager. Anybody can set the
+/// owner references, hence the requests made by other users are not trusted
+pub(crate) fn validated_controller<'a>(
+    metadata: &'a ObjectMeta,
+    user_info: &UserInfo,
+) -> Option<&'a OwnerReference> {
+    if !controller_manager().matches(user_info) {
+        return None;
+    }
+    metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .filter(|owner| owner.controller == Some(true))
+        .find(|owner| {
+            VALIDATED_CONTROLLERS
+                .iter()
+                .any(|(api_version, kind)| owner.api_version == *api_version && owner.kind == *kind)
+        })
+}
+
+/// Whether the resource is the mirror of a static Pod, created by the kubelet
+/// of a node
+pub(crate) fn is_mirror_pod(metadata: &ObjectMeta, user_info: &UserInfo) -> bool {
+    let annotated = metadata
+        .annotations
+        .as_ref()
+        .is_some_and(|annotations| annotations.contains_key(MIRROR_POD_ANNOTATION));
+    annotated && user_info.groups.contains("system:nodes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::user_info;
+    use rstest::*;
+
+    fn owner(api_version: &str, kind: &str, controller: Option<bool>) -> OwnerReference {
+        OwnerReference {
+            api_version: api_version.to_string(),
+            kind: kind.to_string(),
+            name: "nginx".to_string(),
+            uid: "7a3f1c2e-4b5d-4e6f-8a9b-0c1d2e3f4a5b".to_string(),
+            controller,
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case::replica_set_controller(
+        owner("apps/v1", "ReplicaSet", Some(true)),
+        "system:serviceaccount:kube-system:replicaset-controller",
+        true
+    )]
+    #[case::controller_manager(
+        owner("batch/v1", "Job", Some(true)),
+        "system:kube-controller-manager",
+        true
+    )]
+    #[case::not_the_controller(
+        owner("apps/v1", "ReplicaSet", None),
+        "system:serviceaccount:kube-system:replicaset-controller",
+        false
+    )]
+    #[case::not_validated_kind(
+        owner("argoproj.io/v1alpha1", "Rollout", Some(true)),
+        "system:serviceaccount:kube-system:replicaset-controller",
+        false
+    )]
+    #[case::same_kind_of_another_group(
+        owner("apps.kruise.io/v1beta1", "StatefulSet", Some(true)),
+        "system:serviceaccount:kube-system:statefulset-controller",
+        false
+    )]
+    #[case::not_the_controller_manager(owner("apps/v1", "ReplicaSet", Some(true)), "alice", false)]
+    #[case::other_kube_system_account(
+        owner("apps/v1", "ReplicaSet", Some(true)),
+        "system:serviceaccount:kube-system:calico-node",
+        false
+    )]
+    fn controller_validated(
+        #[case] owner: OwnerReference,
+        #[case] username: &str,
+        #[case] expected: bool,
+    ) {
+        let metadata = ObjectMeta {
+            owner_references: Some(vec![owner]),
+            ..Default::default()
+        };
+
+        let controller = validated_controller(&metadata, &user_info(username, &[]));
+        assert_eq!(controller.is_some(), expected);
+    }
+
+    #[rstest]
+    #[case::kubelet(true, &["system:nodes", "system:authenticated"], true)]
+    #[case::not_annotated(false, &["system:nodes", "system:authenticated"], false)]
+    #[case::not_a_node(true, &["system:authenticated"], false)]
+    fn mirror_pod(#[case] annotated: bool, #[case] groups: &[&str], #[case] expected: bool) {
+        let metadata = ObjectMeta {
+            annotations: annotated.then(|| {
+                [(
+                    MIRROR_POD_ANNOTATION.to_string(),
+                    "3f1c2e4b5d4e6f8a".to_string(),
+                )]
+                .into_iter()
+                .collect()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            is_mirror_pod(&metadata, &user_info("system:node:worker-1", groups)),
+            expected
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use chrono::NaiveDate;
+use oci_spec::distribution::Reference;
+
+use crate::{
+    settings::{ImageRef, Settings},
+    validation::{is_allowed_registry, is_allowed_tag},
+    validation_result::Violation,
+};
+
+/// Suggest how to fix the violation of the given image, based on the allow
+/// lists provided by the settings
+pub(crate) fn suggest(
+    image_ref: &Reference,
+    violation: &Violation,
+    settings: &Settings,
+    today: NaiveDate,
+) -> Option<String> {
+    match violation {
+        Violation::RegistryNotAllowed(_) => same_image_elsewhere(image_ref, settings, today),
+        Violation::RepositoryNotAllowed(_) => allowed_namespaces(image_ref, settings),
+        // removing the tag, or pinning to a digest, does not help: the
+        // images without a tag are evaluated as `latest`
+        Violation::TagNotAllowed(_) => most_recent_allowed_tag(image_ref, settings, today),
+        Violation::ImageNotAllowed => most_recent_allowed_tag(image_ref, settings, today)
+            .or_else(|| same_image_elsewhere(image_ref, settings, today)),
+        Violation::DigestRequired => Some("pin to a digest".to_string()),
+        // the violation already names the replacement
+        Violation::DeprecatedRegistry { .. } => None,
+        Violation::Upcoming { violation, .. } | Violation::Grandfathered(violation) => {
+            suggest(image_ref, violation, settings, today)
+        }
+        Violation::ExceptionExpiring { .. } => None,
+    }
+}
+
+/// Look for an allowed image with the same name, served by an allowed
+/// registry. E.g. `registry.corp.com/mirror/nginx` for `docker.io/library/nginx`
+fn same_image_elsewhere(
+    image_ref: &Reference,
+    settings: &Settings,
+    today: NaiveDate,
+) -> Option<String> {
+    let name = image_name(image_ref.repository());
+    settings
+        .images
+        .allow
+        .active(today)
+        .filter(|allowed| {
+            (allowed.registry() != image_ref.registry()
+                || allowed.repository() != image_ref.repository())
+                && image_name(allowed.repository()) == name
+                && is_allowed_registry(allowed.registry(), settings, today)
+        })
+        .map(|allowed| format!("{}/{}", allowed.registry(), allowed.repository()))
+        .min()
+        .map(|alternative| {
+            format!(
+                "use {alternative} instead of {}/{}",
+                image_ref.registry(),
+                image_ref.repository()
+            )
+        })
+}
+
+/// Look for the most recent tag allowed for the repository of the image
+fn most_recent_allowed_tag(
+    image_ref: &Reference,
+    settings: &Settings,
+    today: NaiveDate,
+) -> Option<String> {
+    settings
+        .images
+        .allow
+        .active(today)
+        .filter(|allowed| {
+            allowed.registry() == image_ref.registry()
+                && allowed.repository() == image_ref.repository()
+        })
+        .filter_map(ImageRef::tag)
+        .filter(|tag| Some(*tag) != image_ref.tag() && is_allowed_tag(tag, settings, today))
+        .max_by_key(|tag| (version(tag), tag.to_string()))
+        .map(|tag| format!("most recent allowed tag is {tag}"))
+}
+
+/// List the namespaces allowed for the registry of the image
+fn allowed_namespaces(image_ref: &Reference, settings: &Settings) -> Option<String> {
+    let constraints = settings.repositories.get(image_ref.registry())?;
+    if constraints.namespaces.is_empty() {
+        return constraints
+            .max_depth
+            .map(|max_depth| format!("use a repository with at most {max_depth} path segments",));
+    }
+
+    let mut namespaces: Vec<&str> = constraints.namespaces.iter().map(String::as_str).collect();
+    namespaces.sort();
+    Some(format!(
+        "use a repository inside of {}",
+        namespaces
+            .iter()
+            .map(|namespace| format!("{}/{namespace}", image_ref.registry()))
+            .collect::<Vec<String>>()
+            .join(", ")
+    ))
+}
+
+/// The last path segment of a repository, e.g. `nginx` for `library/nginx`
+fn image_name(repository: &str) -> &str {
+    repository.rsplit('/').next().unwrap_or(repository)
+}
+
+/// The numeric components of a tag, used to sort version-like tags: `1.25.3`
+/// is more recent than `1.9.0`
+fn version(tag: &str) -> Vec<u64> {
+    tag.split(|c: char| !c.is_ascii_digit())
+        .filter_map(|component| component.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use std::{collections::HashMap, str::FromStr};
+
+    use crate::settings::{Images, Registries, RepositoryConstraints};
+
+    fn allow_images(images: &[&str]) -> Images {
+        Images {
+            allow: images
+                .iter()
+                .map(|image| Reference::from_str(image).unwrap().into())
+                .collect(),
+            ..Images::default()
+        }
+    }
+
+    #[rstest]
+    #[case::mirrored_image(
+        "nginx:1.25.3",
+        Violation::RegistryNotAllowed("docker.io".to_string()),
+        Settings {
+            registries: Registries {
+                allow: vec!["registry.corp.com".to_string()].into_iter().collect(),
+                ..Registries::default()
+            },
+            images: allow_images(&["registry.corp.com/mirror/nginx", "registry.corp.com/mirror/redis"]),
+            ..Settings::default()
+        },
+        Some("use registry.corp.com/mirror/nginx instead of docker.io/library/nginx")
+    )]
+    #[case::mirror_not_allowed(
+        "nginx:1.25.3",
+        Violation::RegistryNotAllowed("docker.io".to_string()),
+        Settings {
+            registries: Registries {
+                reject: vec!["docker.io".to_string(), "registry.corp.com".to_string()].into_iter().collect(),
+                ..Registries::default()
+            },
+            images: allow_images(&["registry.corp.com/mirror/nginx"]),
+            ..Settings::default()
+        },
+        None
+    )]
+    #[case::most_recent_allowed_tag(
+        "nginx:latest",
+        Violation::TagNotAllowed("latest".to_string()),
+        Settings {
+            images: allow_images(&["nginx:1.9.0", "nginx:1.25.3", "nginx:1.21.0", "redis:7.2.0"]),
+            ..Settings::default()
+        },
+        Some("most recent allowed tag is 1.25.3")
+    )]
+    #[case::no_allowed_tag(
+        "nginx:latest",
+        Violation::TagNotAllowed("latest".to_string()),
+        Settings::default(),
+        None
+    )]
+    #[case::already_pinned_to_digest(
+        "nginx:latest@sha256:3fc9b689459d738f8c88a3a48aa9e33542016b7a4052e001aaa536fca74813cb",
+        Violation::TagNotAllowed("latest".to_string()),
+        Settings::default(),
+        None
+    )]
+    #[case::image_with_other_allowed_tag(
+        "nginx:1.0.0",
+        Violation::ImageNotAllowed,
+        Settings {
+            images: allow_images(&["nginx:1.25.3"]),
+            ..Settings::default()
+        },
+        Some("most recent allowed tag is 1.25.3")
+    )]
+    #[case::image_allowed_elsewhere(
+        "nginx:1.0.0",
+        Violation::ImageNotAllowed,
+        Settings {
+            images: allow_images(&["registry.corp.com/mirror/nginx:1.25.3"]),
+            ..Settings::default()
+        },
+        Some("use registry.corp.com/mirror/nginx instead of docker.io/library/nginx")
+    )]
+    #[case::no_alternative(
+        "nginx:1.0.0",
+        Violation::ImageNotAllowed,
+        Settings {
+            images: allow_images(&["redis:7.2.0"]),
+            ..Settings::default()
+        },
+        None
+    )]
+    #[case::allowed_namespaces(
+        "ghcr.io/other-org/app:1.0.0",
+        Violation::RepositoryNotAllowed("ghcr.io/other-org/app".to_string()),
+        Settings {
+            repositories: HashMap::from([(
+                "ghcr.io".to_string(),
+                RepositoryConstraints {
+                    namespaces: vec!["my-org".to_string(), "corp".to_string()].into_iter().collect(),
+                    ..RepositoryConstraints::default()
+                },
+            )])
+            .into(),
+            ..Settings::default()
+        },
+        Some("use a repository inside of ghcr.io/corp, ghcr.io/my-org")
+    )]
+    #[case::deprecated_registry(
+        "k8s.gcr.io/pause:3.1",
+        Violation::DeprecatedRegistry {
+            registry: "k8s.gcr.io".to_string(),
+            replacement: "registry.k8s.io".to_string(),
+        },
+        Settings::default(),
+        None
+    )]
+    fn suggest_remediation(
+        #[case] image: &str,
+        #[case] violation: Violation,
+        #[case] settings: Settings,
+        #[case] expected: Option<&str>,
+    ) {
+        let image_ref = Reference::from_str(image).unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2026, 6, 1).unwrap();
+        let suggestion = suggest(&image_ref, &violation, &settings, today);
+        assert_eq!(suggestion.as_deref(), expected);
+    }
+}
@@ -0,0 +1,412 @@
+use std::{borrow::Borrow, collections::HashMap, fmt, hash::Hash, marker::PhantomData};
+
+use chrono::NaiveDate;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Information about why an entry of a rule exists
+#[derive(Deserialize, Serialize, Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RuleMetadata {
+    pub description: Option<String>,
+    /// Who is responsible for the entry, e.g. a team or an email address
+    pub owner: Option<String>,
+    /// The ticket that motivated the entry
+    pub ticket: Option<String>,
+    /// The first day the entry is enforced, e.g. `2027-01-01`. Until then,
+    /// the images it would reject are accepted with a warning
+    pub starts: Option<NaiveDate>,
+    /// The last day the entry is meant to be used, e.g. `2026-12-31`
+    pub expires: Option<NaiveDate>,
+}
+
+impl RuleMetadata {
+    fn is_empty(&self) -> bool {
+        self == &RuleMetadata::default()
+    }
+
+    /// Entries are still valid on the day they expire
+    fn is_expired(&self, today: NaiveDate) -> bool {
+        self.expires.is_some_and(|expires| expires < today)
+    }
+
+    fn is_started(&self, today: NaiveDate) -> bool {
+        self.starts.is_none_or(|starts| starts <= today)
+    }
+
+    /// Whether the entry expires within the given number of days
+    fn expires_within(&self, today: NaiveDate, days: u32) -> bool {
+        self.expires.is_some_and(|expires| {
+            !self.is_expired(today) && (expires - today).num_days() <= i64::from(days)
+        })
+    }
+}
+
+impl fmt::Display for RuleMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let details: Vec<String> = [
+            self.description.clone(),
+            self.owner.as_ref().map(|owner| format!("owner: {owner}")),
+            self.ticket
+                .as_ref()
+                .map(|ticket| format!("ticket: {ticket}")),
+            self.starts.map(|starts| format!("starts: {starts}")),
+            self.expires.map(|expires| format!("expires: {expires}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        write!(f, "{}", details.join(", "))
+    }
+}
+
+/// The name of the field holding the value of an entry written in the object
+/// form, e.g. `registry` for `{"registry": "quay.io", "owner": "team-a"}`
+pub(crate) trait EntryKey {
+    const KEY: &'static str;
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RegistryKey;
+impl EntryKey for RegistryKey {
+    const KEY: &'static str = "registry";
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TagKey;
+impl EntryKey for TagKey {
+    const KEY: &'static str = "tag";
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ImageKey;
+impl EntryKey for ImageKey {
+    const KEY: &'static str = "image";
+}
+
+/// The entries of an allow or reject list. Each entry can be either a plain
+/// value, or an object holding the value together with its metadata:
+///
+/// ```yaml
+/// - quay.io
+/// - registry: ghcr.io
+///   description: images built by the CI
+///   owner: platform-team
+///   ticket: OPS-1234
+///   expires: 2026-12-31
+/// ```
+#[derive(Debug, Clone)]
+pub(crate) struct RuleSet<T, K> {
+    entries: HashMap<T, RuleMetadata>,
+    key: PhantomData<K>,
+}
+
+impl<T, K> Default for RuleSet<T, K> {
+    fn default() -> Self {
+        RuleSet {
+            entries: HashMap::new(),
+            key: PhantomData,
+        }
+    }
+}
+
+impl<T: Eq + Hash, K> RuleSet<T, K> {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether the list has an entry for the value that is enforced on the
+    /// given day. Expired entries are ignored: an image allowed by an expired
+    /// exception falls back to the other rules. Entries starting after the
+    /// given day are ignored as well
+    pub fn applies<Q>(&self, value: &Q, today: NaiveDate) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries
+            .get(value)
+            .is_some_and(|metadata| metadata.is_started(today) && !metadata.is_expired(today))
+    }
+
+    /// The day the entry of the value starts to be enforced, when it's after
+    /// the given day
+    pub fn scheduled<Q>(&self, value: &Q, today: NaiveDate) -> Option<NaiveDate>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries
+            .get(value)
+            .filter(|metadata| !metadata.is_started(today))
+            .and_then(|metadata| metadata.starts)
+    }
+
+    /// The metadata of the entry, `None` when the entry does not exist or
+    /// has no metadata
+    pub fn metadata<Q>(&self, value: &Q) -> Option<&RuleMetadata>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries
+            .get(value)
+            .filter(|metadata| !metadata.is_empty())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries.keys()
+    }
+
+    /// Replace every entry with the value returned by `f`, keeping its
+    /// metadata
+    pub fn map<F: Fn(&T) -> T>(&self, f: F) -> Self {
+        RuleSet {
+            entries: self
+                .entries
+                .iter()
+                .map(|(value, metadata)| (f(value), metadata.clone()))
+                .collect(),
+            key: PhantomData,
+        }
+    }
+
+    /// The expiration date of the entry, when it expires within the given
+    /// number of days
+    pub fn expiring<Q>(&self, value: &Q, today: NaiveDate, days: u32) -> Option<NaiveDate>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries
+            .get(value)
+            .filter(|metadata| metadata.expires_within(today, days))
+            .and_then(|metadata| metadata.expires)
+    }
+
+    /// The entries enforced on the given day
+    pub fn active(&self, today: NaiveDate) -> impl Iterator<Item = &T> {
+        self.entries
+            .iter()
+            .filter(move |(_, metadata)| metadata.is_started(today) && !metadata.is_expired(today))
+            .map(|(value, _)| value)
+    }
+
+    /// The entries that expired before the given day
+    pub fn expired(&self, today: NaiveDate) -> impl Iterator<Item = &T> {
+        self.entries
+            .iter()
+            .filter(move |(_, metadata)| metadata.is_expired(today))
+            .map(|(value, _)| value)
+    }
+
+    /// The entries starting after their expiration
+    pub fn misscheduled(&self) -> impl Iterator<Item = &T> {
+        self.entries
+            .iter()
+            .filter(|(_, metadata)| {
+                metadata
+                    .starts
+                    .zip(metadata.expires)
+                    .is_some_and(|(starts, expires)| starts > expires)
+            })
+            .map(|(value, _)| value)
+    }
+}
+
+impl<T: Eq + Hash, K> FromIterator<T> for RuleSet<T, K> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        RuleSet {
+            entries: iter
+                .into_iter()
+                .map(|value| (value, RuleMetadata::default()))
+                .collect(),
+            key: PhantomData,
+        }
+    }
+}
+
+impl<T, K> From<HashMap<T, RuleMetadata>> for RuleSet<T, K> {
+    fn from(entries: HashMap<T, RuleMetadata>) -> Self {
+        RuleSet {
+            entries,
+            key: PhantomData,
+        }
+    }
+}
+
+impl<'de, T, K> Deserialize<'de> for RuleSet<T, K>
+where
+    T: DeserializeOwned + Eq + Hash,
+    K: EntryKey,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let entries = Vec::<serde_json::Value>::deserialize(deserializer)?;
+
+        entries
+            .into_iter()
+            .map(|entry| match entry {
+                serde_json::Value::Object(mut fields) => {
+                    let value = fields.remove(K::KEY).ok_or_else(|| {
+                        serde::de::Error::custom(format!("missing field `{}`", K::KEY))
+                    })?;
+                    let value = T::deserialize(value).map_err(serde::de::Error::custom)?;
+                    let metadata = RuleMetadata::deserialize(serde_json::Value::Object(fields))
+                        .map_err(serde::de::Error::custom)?;
+                    Ok((value, metadata))
+                }
+                value => {
+                    let value = T::deserialize(value).map_err(serde::de::Error::custom)?;
+                    Ok((value, RuleMetadata::default()))
+                }
+            })
+            .collect::<Result<HashMap<T, RuleMetadata>, D::Error>>()
+            .map(RuleSet::from)
+    }
+}
+
+impl<T, K> Serialize for RuleSet<T, K>
+where
+    T: Serialize,
+    K: EntryKey,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let entries = self
+            .entries
+            .iter()
+            .map(|(value, metadata)| {
+                let value = serde_json::to_value(value).map_err(serde::ser::Error::custom)?;
+                if metadata.is_empty() {
+                    return Ok(value);
+                }
+                let mut entry =
+                    serde_json::to_value(metadata).map_err(serde::ser::Error::custom)?;
+                if let serde_json::Value::Object(fields) = &mut entry {
+                    fields.retain(|_, field| !field.is_null());
+                    fields.insert(K::KEY.to_string(), value);
+                }
+                Ok(entry)
+            })
+            .collect::<Result<Vec<serde_json::Value>, S::Error>>()?;
+        entries.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::plain_values(r#"["quay.io", "ghcr.io"]"#, vec![("quay.io", None), ("ghcr.io", None)])]
+    #[case::object_form(
+        r#"[
+            "quay.io",
+            {"registry": "ghcr.io", "description": "images built by the CI", "owner": "platform-team", "ticket": "OPS-1234", "expires": "2026-12-31"}
+        ]"#,
+        vec![
+            ("quay.io", None),
+            ("ghcr.io", Some("images built by the CI, owner: platform-team, ticket: OPS-1234, expires: 2026-12-31")),
+        ]
+    )]
+    #[case::object_form_without_metadata(r#"[{"registry": "ghcr.io"}]"#, vec![("ghcr.io", None)])]
+    fn deserialize_rule_set(#[case] input: &str, #[case] expected: Vec<(&str, Option<&str>)>) {
+        let rule_set: RuleSet<String, RegistryKey> = serde_json::from_str(input).unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        assert_eq!(rule_set.active(today).count(), expected.len());
+        for (value, metadata) in expected {
+            assert!(rule_set.applies(value, today), "{value} not found");
+            assert_eq!(
+                rule_set
+                    .metadata(&value.to_string())
+                    .map(|metadata| metadata.to_string())
+                    .as_deref(),
+                metadata
+            );
+        }
+    }
+
+    #[rstest]
+    #[case::missing_value(r#"[{"owner": "platform-team"}]"#)]
+    #[case::invalid_date(r#"[{"registry": "ghcr.io", "expires": "next year"}]"#)]
+    #[case::unknown_value_type(r#"[42]"#)]
+    #[case::misspelled_field(r#"[{"registry": "docker.io", "expire": "2026-12-31"}]"#)]
+    fn deserialize_invalid_rule_set(#[case] input: &str) {
+        let rule_set: Result<RuleSet<String, RegistryKey>, _> = serde_json::from_str(input);
+        assert!(rule_set.is_err(), "was supposed to be invalid");
+    }
+
+    #[test]
+    fn serialize_rule_set() {
+        let rule_set: RuleSet<String, TagKey> = HashMap::from([(
+            "latest".to_string(),
+            RuleMetadata {
+                owner: Some("platform-team".to_string()),
+                ..RuleMetadata::default()
+            },
+        )])
+        .into();
+
+        assert_eq!(
+            serde_json::to_value(&rule_set).unwrap(),
+            serde_json::json!([{"tag": "latest", "owner": "platform-team"}])
+        );
+    }
+
+    #[test]
+    fn expired_entries() {
+        let rule_set: RuleSet<String, TagKey> = serde_json::from_str(
+            r#"["latest", {"tag": "edge", "expires": "2026-01-31"}, {"tag": "nightly", "expires": "2026-02-01"}]"#,
+        )
+        .unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        let expired: Vec<&String> = rule_set.expired(today).collect();
+        assert_eq!(expired, vec!["edge"]);
+        assert!(rule_set.applies("latest", today));
+        assert!(!rule_set.applies("edge", today));
+        assert!(rule_set.applies("nightly", today));
+    }
+
+    #[test]
+    fn scheduled_entries() {
+        let rule_set: RuleSet<String, RegistryKey> = serde_json::from_str(
+            r#"["quay.io", {"registry": "docker.io", "starts": "2027-01-01"}, {"registry": "ghcr.io", "starts": "2026-12-31"}]"#,
+        )
+        .unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2026, 12, 31).unwrap();
+        assert!(rule_set.applies("quay.io", today));
+        assert!(!rule_set.applies("docker.io", today));
+        assert!(rule_set.applies("ghcr.io", today));
+        assert_eq!(
+            rule_set.scheduled("docker.io", today),
+            NaiveDate::from_ymd_opt(2027, 1, 1)
+        );
+        assert_eq!(rule_set.scheduled("ghcr.io", today), None);
+        assert_eq!(rule_set.scheduled("quay.io", today), None);
+    }
+
+    #[rstest]
+    #[case::no_expiration(None, false)]
+    #[case::expired(Some((2026, 1, 31)), false)]
+    #[case::expires_today(Some((2026, 2, 1)), true)]
+    #[case::expires_at_the_end_of_the_window(Some((2026, 3, 3)), true)]
+    #[case::expires_after_the_window(Some((2026, 3, 4)), false)]
+    fn expires_within_days(#[case] expires: Option<(i32, u32, u32)>, #[case] expected: bool) {
+        let metadata = RuleMetadata {
+            expires: expires
+                .map(|(year, month, day)| NaiveDate::from_ymd_opt(year, month, day).unwrap()),
+            ..RuleMetadata::default()
+        };
+
+        let today = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        assert_eq!(metadata.expires_within(today, 30), expected);
+    }
+}
@@ -1,17 +1,45 @@
-use std::{collections::HashSet, str::FromStr};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::Hash,
+    str::FromStr,
+};
 
-use kubewarden_policy_sdk::settings::Validatable;
+use chrono::{NaiveDate, Utc};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta};
+use kubewarden_policy_sdk::{request::UserInfo, settings::Validatable};
 use oci_spec::distribution::Reference;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use slog::warn;
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+use crate::{
+    glob,
+    json_path::JsonPath,
+    label_selector,
+    rule_set::{ImageKey, RegistryKey, RuleSet, TagKey},
+    template,
+    validation_result::ContainerType,
+    LOG_DRAIN,
+};
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
 #[serde(default)]
 pub(crate) struct Registries {
-    pub allow: HashSet<String>,
-    pub reject: HashSet<String>,
+    pub allow: RuleSet<String, RegistryKey>,
+    pub reject: RuleSet<String, RegistryKey>,
+    pub action: Action,
 }
 
 impl Registries {
+    /// Replace every entry of the lists with the value returned by `f`
+    fn map<F: Fn(&String) -> String>(&self, f: F) -> Self {
+        Registries {
+            allow: self.allow.map(&f),
+            reject: self.reject.map(&f),
+            action: self.action,
+        }
+    }
+
     fn validate(&self) -> Result<(), String> {
         if !self.allow.is_empty() && !self.reject.is_empty() {
             return Err("only one of registries allow or reject can be provided".to_string());
@@ -20,10 +48,11 @@ impl Registries {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
 #[serde(default)]
 pub(crate) struct Tags {
-    pub reject: HashSet<String>,
+    pub reject: RuleSet<String, TagKey>,
+    pub action: Action,
 }
 
 impl Tags {
@@ -61,6 +90,12 @@ impl ImageRef {
     pub fn registry(&self) -> &str {
         self.0.registry()
     }
+    pub fn tag(&self) -> Option<&str> {
+        self.0.tag()
+    }
+    pub fn whole(&self) -> String {
+        self.0.whole()
+    }
 }
 
 impl From<Reference> for ImageRef {
@@ -91,11 +126,14 @@ impl Serialize for ImageRef {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
-#[serde(default)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
 pub(crate) struct Images {
-    pub allow: HashSet<ImageRef>,
-    pub reject: HashSet<ImageRef>,
+    pub allow: RuleSet<ImageRef, ImageKey>,
+    pub reject: RuleSet<ImageRef, ImageKey>,
+    /// Reject the images that are not pinned to a digest
+    pub require_digest: bool,
+    pub action: Action,
 }
 
 impl Images {
@@ -108,20 +146,910 @@ impl Images {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+/// Constraints applied to the repositories hosted by a registry
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct RepositoryConstraints {
+    /// The leading path segments the repository must start with, e.g.
+    /// `library` or `my-org/team`. Any namespace is allowed when empty
+    pub namespaces: HashSet<String>,
+    /// The maximum number of path segments of the repository
+    pub max_depth: Option<usize>,
+    pub action: Action,
+}
+
+impl RepositoryConstraints {
+    fn validate(&self, registry: &str) -> Result<(), String> {
+        let invalid_namespaces: Vec<&String> = self
+            .namespaces
+            .iter()
+            .filter(|namespace| namespace.split('/').any(str::is_empty))
+            .collect();
+        if !invalid_namespaces.is_empty() {
+            return Err(format!(
+                "repositories namespaces {invalid_namespaces:?} of registry {registry} are invalid, they must be non empty paths without leading or trailing slashes",
+            ));
+        }
+
+        if let Some(max_depth) = self.max_depth {
+            if max_depth == 0 {
+                return Err(format!(
+                    "repositories maxDepth of registry {registry} must be greater than 0"
+                ));
+            }
+            let too_deep_namespaces: Vec<&String> = self
+                .namespaces
+                .iter()
+                .filter(|namespace| namespace.split('/').count() >= max_depth)
+                .collect();
+            if !too_deep_namespaces.is_empty() {
+                return Err(format!(
+                    "repositories namespaces {too_deep_namespaces:?} of registry {registry} cannot match any repository with a maxDepth of {max_depth}",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Repository constraints, indexed by registry
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(transparent)]
+pub(crate) struct Repositories(HashMap<String, RepositoryConstraints>);
+
+impl Repositories {
+    pub fn get(&self, registry: &str) -> Option<&RepositoryConstraints> {
+        self.0.get(registry)
+    }
+
+    /// Replace every registry and namespace with the value returned by `f`
+    fn map<F: Fn(&String) -> String>(&self, f: F) -> Self {
+        Repositories(
+            self.0
+                .iter()
+                .map(|(registry, constraints)| {
+                    let constraints = RepositoryConstraints {
+                        namespaces: constraints.namespaces.iter().map(&f).collect(),
+                        ..constraints.clone()
+                    };
+                    (f(registry), constraints)
+                })
+                .collect(),
+        )
+    }
+
+    /// The registries and namespaces of the constraints
+    fn entries(&self) -> impl Iterator<Item = &String> {
+        self.0.iter().flat_map(|(registry, constraints)| {
+            std::iter::once(registry).chain(constraints.namespaces.iter())
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let errors: Vec<String> = self
+            .0
+            .iter()
+            .filter_map(|(registry, constraints)| constraints.validate(registry).err())
+            .collect();
+
+        if !errors.is_empty() {
+            return Err(errors.join(", "));
+        }
+        Ok(())
+    }
+}
+
+impl From<HashMap<String, RepositoryConstraints>> for Repositories {
+    fn from(constraints: HashMap<String, RepositoryConstraints>) -> Self {
+        Repositories(constraints)
+    }
+}
+
+/// What to do when an image violates a rule
+#[derive(Deserialize, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Action {
+    /// Reject the request
+    #[default]
+    Deny,
+    /// Accept the request, report the violation as a warning
+    Warn,
+    /// Accept the request, report the violation inside of the audit annotations
+    Audit,
+}
+
+/// An entry of the deprecated registries table
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DeprecatedRegistry {
+    /// The deprecated registry, optionally followed by a repository
+    /// namespace. E.g. `k8s.gcr.io` or `gcr.io/google_containers`
+    pub registry: String,
+    /// The registry to be used instead
+    pub replacement: String,
+    /// Overrides the default action of the deprecated registries
+    pub action: Option<Action>,
+}
+
+/// Settings of the deprecated registries detection. The entries are merged
+/// with the built-in table, overriding the built-in entries with the same
+/// registry
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(default)]
+pub(crate) struct DeprecatedRegistries {
+    pub action: Action,
+    pub registries: Vec<DeprecatedRegistry>,
+}
+
+impl Default for DeprecatedRegistries {
+    fn default() -> Self {
+        DeprecatedRegistries {
+            action: Action::Warn,
+            registries: Vec::new(),
+        }
+    }
+}
+
+impl DeprecatedRegistries {
+    fn validate(&self) -> Result<(), String> {
+        let invalid_registries: Vec<&str> = self
+            .registries
+            .iter()
+            .filter(|entry| {
+                entry.registry.is_empty()
+                    || entry.registry.ends_with('/')
+                    || entry.replacement.is_empty()
+            })
+            .map(|entry| entry.registry.as_str())
+            .collect();
+
+        if !invalid_registries.is_empty() {
+            return Err(format!(
+                "deprecated registries {invalid_registries:?} are invalid, they must have a registry without trailing slashes and a replacement",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Settings of the entries of the allow and reject lists with an `expires`
+/// date
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct Expiration {
+    /// Number of days before the expiration of an allow list entry starting
+    /// from which the images it allows produce warnings
+    pub warning_days: u32,
+}
+
+impl Default for Expiration {
+    fn default() -> Self {
+        Expiration { warning_days: 30 }
+    }
+}
+
+/// The placeholders that can be used inside of the rejection message template
+pub(crate) const REJECTION_MESSAGE_PLACEHOLDERS: &[&str] = &[
+    "container",
+    "containerType",
+    "fieldPath",
+    "image",
+    "registry",
+    "tag",
+    "rule",
+    "violation",
+    "suggestion",
+    "description",
+    "owner",
+    "ticket",
+    "expires",
+];
+
+/// Customization of the response sent when a request is rejected
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct RejectionMessage {
+    /// Template rendered once per violation, e.g.
+    /// `{{image}} is not approved, see https://wiki.corp.com/images`. The
+    /// built-in message is used when not provided
+    pub template: Option<String>,
+    /// The HTTP status code of the rejection
+    pub code: Option<u16>,
+}
+
+impl RejectionMessage {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(message_template) = &self.template {
+            if message_template.trim().is_empty() {
+                return Err("rejectionMessage template cannot be empty".to_string());
+            }
+            let unknown_placeholders: Vec<&str> = template::placeholders(message_template)
+                .into_iter()
+                .filter(|placeholder| !REJECTION_MESSAGE_PLACEHOLDERS.contains(placeholder))
+                .collect();
+            if !unknown_placeholders.is_empty() {
+                return Err(format!(
+                    "rejectionMessage template placeholders {unknown_placeholders:?} are unknown, supported placeholders are {REJECTION_MESSAGE_PLACEHOLDERS:?}",
+                ));
+            }
+        }
+
+        if let Some(code) = self.code {
+            if !(400..=599).contains(&code) {
+                return Err(format!(
+                    "rejectionMessage code {code} is invalid, it must be an HTTP error status code (400-599)"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The admission request details that can be used inside of the rule entries
+/// through placeholders like `{{namespace}}`, `{{labels.team}}` or
+/// `{{namespaceLabels.team}}`
+pub(crate) struct RuleContext<'a> {
+    /// Namespace of the request, empty for cluster-scoped resources
+    pub namespace: &'a str,
+    /// Labels of the validated resource
+    pub labels: &'a BTreeMap<String, String>,
+    /// Labels of the Namespace of the request, only fetched when some
+    /// placeholder needs them
+    pub namespace_labels: &'a BTreeMap<String, String>,
+}
+
+impl RuleContext<'_> {
+    /// The value of the placeholder, if known for the request
+    fn lookup(&self, placeholder: &str) -> Option<String> {
+        if placeholder == "namespace" {
+            return (!self.namespace.is_empty()).then(|| self.namespace.to_string());
+        }
+        let (labels, key) = placeholder
+            .strip_prefix(LABELS_PLACEHOLDER_PREFIX)
+            .map(|key| (self.labels, key))
+            .or_else(|| {
+                placeholder
+                    .strip_prefix(NAMESPACE_LABELS_PLACEHOLDER_PREFIX)
+                    .map(|key| (self.namespace_labels, key))
+            })?;
+        labels.get(key).cloned()
+    }
+}
+
+/// Prefix of the placeholders filled from the labels of the resource
+const LABELS_PLACEHOLDER_PREFIX: &str = "labels.";
+
+/// Prefix of the placeholders filled from the labels of the Namespace
+const NAMESPACE_LABELS_PLACEHOLDER_PREFIX: &str = "namespaceLabels.";
+
+/// Whether the placeholder can be used inside of the rule entries
+fn is_rule_placeholder(placeholder: &str) -> bool {
+    placeholder == "namespace"
+        || [
+            LABELS_PLACEHOLDER_PREFIX,
+            NAMESPACE_LABELS_PLACEHOLDER_PREFIX,
+        ]
+        .iter()
+        .any(|prefix| {
+            placeholder
+                .strip_prefix(prefix)
+                .is_some_and(|key| !key.is_empty())
+        })
+}
+
+/// The users issuing the requests a rule applies to. A user matches when its
+/// username, one of its groups or its service account is listed
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct Subjects {
+    /// Usernames, either exact names or glob patterns
+    pub users: Vec<String>,
+    /// Groups, either exact names or glob patterns
+    pub groups: Vec<String>,
+    /// Service accounts written as `<namespace>/<name>`, either exact names
+    /// or glob patterns like `argocd/*`
+    pub service_accounts: Vec<String>,
+}
+
+impl Subjects {
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty() && self.groups.is_empty() && self.service_accounts.is_empty()
+    }
+
+    pub fn matches(&self, user_info: &UserInfo) -> bool {
+        let service_account = user_info
+            .username
+            .strip_prefix("system:serviceaccount:")
+            .map(|service_account| service_account.replacen(':', "/", 1));
+
+        self.users
+            .iter()
+            .any(|pattern| glob::matches(pattern, &user_info.username))
+            || self.groups.iter().any(|pattern| {
+                user_info
+                    .groups
+                    .iter()
+                    .any(|group| glob::matches(pattern, group))
+            })
+            || service_account.is_some_and(|service_account| {
+                self.service_accounts
+                    .iter()
+                    .any(|pattern| glob::matches(pattern, &service_account))
+            })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self
+            .users
+            .iter()
+            .chain(self.groups.iter())
+            .any(String::is_empty)
+        {
+            return Err("users and groups cannot be empty".to_string());
+        }
+        let invalid_service_accounts: Vec<&String> = self
+            .service_accounts
+            .iter()
+            .filter(|service_account| {
+                service_account
+                    .split_once('/')
+                    .is_none_or(|(namespace, name)| {
+                        namespace.is_empty() || name.is_empty() || name.contains('/')
+                    })
+            })
+            .collect();
+        if !invalid_service_accounts.is_empty() {
+            return Err(format!(
+                "serviceAccounts {invalid_service_accounts:?} are invalid, they must be written as <namespace>/<name>"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Rule sections replacing the whole sections of the base settings
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct RuleOverrides {
+    pub registries: Option<Registries>,
+    pub tags: Option<Tags>,
+    pub images: Option<Images>,
+    pub repositories: Option<Repositories>,
+    pub deprecated_registries: Option<DeprecatedRegistries>,
+}
+
+impl RuleOverrides {
+    /// The entries of the `registries` and `repositories` sections, which can
+    /// contain placeholders
+    fn templated_entries(&self) -> impl Iterator<Item = &String> {
+        self.registries
+            .iter()
+            .flat_map(|registries| registries.allow.iter().chain(registries.reject.iter()))
+            .chain(self.repositories.iter().flat_map(Repositories::entries))
+    }
+
+    /// Replace every entry of the `registries` and `repositories` sections
+    /// with the value returned by `f`
+    fn map<F: Fn(&String) -> String>(&self, f: F) -> Self {
+        RuleOverrides {
+            registries: self
+                .registries
+                .as_ref()
+                .map(|registries| registries.map(&f)),
+            repositories: self
+                .repositories
+                .as_ref()
+                .map(|repositories| repositories.map(&f)),
+            ..self.clone()
+        }
+    }
+}
+
+/// Rules specific to each kind of container. Containers without specific
+/// rules are evaluated against the base settings
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct ContainerTypeRules {
+    pub init_containers: Option<RuleOverrides>,
+    /// Init containers with an `Always` restart policy
+    pub sidecar_containers: Option<RuleOverrides>,
+    pub containers: Option<RuleOverrides>,
+    pub ephemeral_containers: Option<RuleOverrides>,
+    /// Volumes mounting OCI images or artifacts
+    pub image_volumes: Option<RuleOverrides>,
+}
+
+impl ContainerTypeRules {
+    pub fn get(&self, container_type: ContainerType) -> Option<&RuleOverrides> {
+        match container_type {
+            ContainerType::InitContainer => self.init_containers.as_ref(),
+            ContainerType::SidecarContainer => self.sidecar_containers.as_ref(),
+            ContainerType::Container => self.containers.as_ref(),
+            ContainerType::EphemeralContainer => self.ephemeral_containers.as_ref(),
+            ContainerType::ImageVolume => self.image_volumes.as_ref(),
+            // the overlays selected by kind provide the rules of the custom
+            // resources
+            ContainerType::ResourceImage => None,
+        }
+    }
+
+    /// The rules of each kind of container, together with the name of the
+    /// settings field holding them
+    fn iter(&self) -> impl Iterator<Item = (&'static str, &RuleOverrides)> {
+        [
+            ("initContainers", &self.init_containers),
+            ("sidecarContainers", &self.sidecar_containers),
+            ("containers", &self.containers),
+            ("ephemeralContainers", &self.ephemeral_containers),
+            ("imageVolumes", &self.image_volumes),
+        ]
+        .into_iter()
+        .filter_map(|(field, rules)| rules.as_ref().map(|rules| (field, rules)))
+    }
+
+    /// Replace every entry of the `registries` and `repositories` sections
+    /// with the value returned by `f`
+    fn map<F: Fn(&String) -> String>(&self, f: F) -> Self {
+        let map = |rules: &Option<RuleOverrides>| rules.as_ref().map(|rules| rules.map(&f));
+        ContainerTypeRules {
+            init_containers: map(&self.init_containers),
+            sidecar_containers: map(&self.sidecar_containers),
+            containers: map(&self.containers),
+            ephemeral_containers: map(&self.ephemeral_containers),
+            image_volumes: map(&self.image_volumes),
+        }
+    }
+}
+
+/// Rules specific to some containers, selected by their name, like the
+/// sidecars injected by a service mesh
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct ContainerOverride {
+    /// The names of the containers, either exact names or glob patterns
+    pub names: Vec<String>,
+    /// The namespaces the override is restricted to, either exact names or
+    /// glob patterns. The override applies to all the namespaces when empty
+    pub namespaces: Vec<String>,
+    /// Do not evaluate the images of the containers
+    pub exempt: bool,
+    /// Rules replacing the ones of the containers, ignored when exempted
+    #[serde(flatten)]
+    pub rules: RuleOverrides,
+}
+
+impl ContainerOverride {
+    fn matches_namespace(&self, namespace: &str) -> bool {
+        self.namespaces.is_empty()
+            || self
+                .namespaces
+                .iter()
+                .any(|pattern| glob::matches(pattern, namespace))
+    }
+
+    fn matches_container(&self, name: &str) -> bool {
+        self.names
+            .iter()
+            .any(|pattern| glob::matches(pattern, name))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.names.is_empty() {
+            return Err("names must be provided".to_string());
+        }
+        if self
+            .names
+            .iter()
+            .chain(self.namespaces.iter())
+            .any(String::is_empty)
+        {
+            return Err("names and namespaces cannot be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Rules replacing the ones of the base settings for the resources matching
+/// the overlay. Each section provided by the overlay replaces the whole
+/// section of the base settings
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct Overlay {
+    /// The namespaces the overlay applies to, either exact names or glob
+    /// patterns like `sandbox-*`
+    pub namespaces: Vec<String>,
+    /// Selector of the labels of the Namespace objects the overlay applies
+    /// to. Requires the policy to fetch the Namespace from the cluster
+    pub namespace_selector: Option<LabelSelector>,
+    /// The users whose requests the overlay applies to
+    #[serde(flatten)]
+    pub subjects: Subjects,
+    /// The kinds of resources the overlay applies to, e.g. `CronJob`
+    pub kinds: Vec<String>,
+    #[serde(flatten)]
+    pub rules: RuleOverrides,
+    pub rejection_message: Option<RejectionMessage>,
+}
+
+impl Overlay {
+    /// Whether the overlay applies to a request about a resource of one of the
+    /// given kinds, e.g. the kind of the resource and the one of its
+    /// controller, made by the given user inside of the given namespace. All
+    /// the provided criteria must be satisfied
+    pub fn matches(
+        &self,
+        kinds: &[&str],
+        namespace: &str,
+        labels: &BTreeMap<String, String>,
+        user_info: &UserInfo,
+    ) -> bool {
+        let kind_matches =
+            self.kinds.is_empty() || self.kinds.iter().any(|name| kinds.contains(&name.as_str()));
+        let name_matches = self.namespaces.is_empty()
+            || self
+                .namespaces
+                .iter()
+                .any(|pattern| glob::matches(pattern, namespace));
+        let labels_match = self
+            .namespace_selector
+            .as_ref()
+            .is_none_or(|selector| label_selector::matches(selector, labels));
+        let user_matches = self.subjects.is_empty() || self.subjects.matches(user_info);
+
+        kind_matches && name_matches && labels_match && user_matches
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.namespaces.is_empty()
+            && self.namespace_selector.is_none()
+            && self.subjects.is_empty()
+            && self.kinds.is_empty()
+        {
+            return Err(
+                "namespaces, namespaceSelector, users, groups, serviceAccounts or kinds must be provided"
+                    .to_string(),
+            );
+        }
+        if self.namespaces.iter().any(String::is_empty) {
+            return Err("namespaces cannot be empty".to_string());
+        }
+        if self.kinds.iter().any(String::is_empty) {
+            return Err("kinds cannot be empty".to_string());
+        }
+        if let Some(selector) = &self.namespace_selector {
+            label_selector::validate(selector)
+                .map_err(|error| format!("namespaceSelector: {error}"))?;
+        }
+        self.subjects.validate()
+    }
+}
+
+/// Workloads the policy does not evaluate, like vendor operators that cannot
+/// be modified
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct Exemption {
+    /// Name of the exemption, reported inside of the audit annotations
+    pub name: String,
+    /// Selector of the labels of the exempted workloads
+    pub label_selector: Option<LabelSelector>,
+    /// Selector of the annotations of the exempted workloads, written like a
+    /// label selector
+    pub annotation_selector: Option<LabelSelector>,
+    /// The users whose requests are exempted
+    #[serde(flatten)]
+    pub subjects: Subjects,
+}
+
+impl Exemption {
+    /// Whether the exemption applies to a resource with the given metadata,
+    /// e.g. the one of the resource and the one of its Pod template, created
+    /// by the given user. The resource is exempted when one of the metadata
+    /// satisfies all the selectors, and the user is one of the subjects
+    pub fn matches(&self, metadata: &[&ObjectMeta], user_info: &UserInfo) -> bool {
+        if !self.subjects.is_empty() && !self.subjects.matches(user_info) {
+            return false;
+        }
+        let empty = BTreeMap::new();
+        metadata.iter().any(|metadata| {
+            let labels = metadata.labels.as_ref().unwrap_or(&empty);
+            let annotations = metadata.annotations.as_ref().unwrap_or(&empty);
+            self.label_selector
+                .as_ref()
+                .is_none_or(|selector| label_selector::matches(selector, labels))
+                && self
+                    .annotation_selector
+                    .as_ref()
+                    .is_none_or(|selector| label_selector::matches(selector, annotations))
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("name must be provided".to_string());
+        }
+        if self.label_selector.is_none()
+            && self.annotation_selector.is_none()
+            && self.subjects.is_empty()
+        {
+            return Err(format!(
+                "{}: labelSelector, annotationSelector, users, groups or serviceAccounts must be provided",
+                self.name
+            ));
+        }
+        let selectors = [
+            ("labelSelector", &self.label_selector),
+            ("annotationSelector", &self.annotation_selector),
+        ];
+        for (field, selector) in selectors {
+            if let Some(selector) = selector {
+                label_selector::validate(selector)
+                    .map_err(|error| format!("{}: {field}: {error}", self.name))?;
+            }
+        }
+        self.subjects
+            .validate()
+            .map_err(|error| format!("{}: {error}", self.name))
+    }
+}
+
+/// Where the images of a custom resource are found
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct CustomResource {
+    /// The API group of the resource, empty for the core group
+    pub group: String,
+    pub kind: String,
+    /// The paths of the image fields, e.g. `spec.steps[*].image`
+    pub images: Vec<JsonPath>,
+    /// The paths of the Pod specifications embedded inside of the resource,
+    /// e.g. `spec.podTemplate.spec`
+    pub pod_specs: Vec<JsonPath>,
+}
+
+impl CustomResource {
+    fn validate(&self) -> Result<(), String> {
+        if self.kind.is_empty() {
+            return Err("kind must be provided".to_string());
+        }
+        if self.images.is_empty() && self.pod_specs.is_empty() {
+            return Err(format!(
+                "{}: images or podSpecs must be provided",
+                self.kind
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// How to handle the mirror Pods created by the kubelet for the static Pods
+#[derive(Deserialize, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MirrorPods {
+    /// Evaluate them like any other Pod
+    #[default]
+    Evaluate,
+    /// Evaluate them, report the violations as warnings
+    Warn,
+    /// Accept them without evaluating them
+    Skip,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
 pub(crate) struct Settings {
     pub registries: Registries,
     pub tags: Tags,
     pub images: Images,
+    pub repositories: Repositories,
+    pub deprecated_registries: DeprecatedRegistries,
+    pub rejection_message: RejectionMessage,
+    pub expiration: Expiration,
+    /// Rules specific to some namespaces, applied in order on top of the
+    /// base settings
+    pub overlays: Vec<Overlay>,
+    /// Workloads that are not evaluated
+    pub exemptions: Vec<Exemption>,
+    /// The users allowed to bypass the policy through the break-glass
+    /// annotation. Nobody is allowed when empty
+    pub break_glass: Subjects,
+    /// Rules specific to each kind of container
+    pub container_types: ContainerTypeRules,
+    /// Rules specific to some containers, selected by their name. They take
+    /// precedence over the rules of the kinds of container
+    pub container_overrides: Vec<ContainerOverride>,
+    /// On updates, only warn about the images the containers were already
+    /// using
+    pub grandfather_existing_images: bool,
+    /// Accept the resources created by the controllers the policy already
+    /// validates, e.g. the Pods of a ReplicaSet
+    pub skip_controlled_resources: bool,
+    pub mirror_pods: MirrorPods,
+    /// Resources the policy does not know about, evaluated through the paths
+    /// of their images
+    pub custom_resources: Vec<CustomResource>,
 }
 
-impl Validatable for Settings {
-    fn validate(&self) -> Result<(), String> {
+impl Settings {
+    /// The first exemption applying to a resource with the given metadata,
+    /// created by the given user
+    pub fn exemption(&self, metadata: &[&ObjectMeta], user_info: &UserInfo) -> Option<&Exemption> {
+        self.exemptions
+            .iter()
+            .find(|exemption| exemption.matches(metadata, user_info))
+    }
+
+    /// How to find the images of the resources of the given group and kind,
+    /// `None` when they are not evaluated
+    pub fn custom_resource(&self, group: &str, kind: &str) -> Option<&CustomResource> {
+        self.custom_resources
+            .iter()
+            .find(|custom_resource| custom_resource.group == group && custom_resource.kind == kind)
+    }
+
+    /// Whether some overlay selects the namespaces by their labels, or some
+    /// rule entry is filled from them, hence the Namespace object must be
+    /// fetched from the cluster
+    pub fn needs_namespace_labels(&self) -> bool {
+        let base_entries = self
+            .registries
+            .allow
+            .iter()
+            .chain(self.registries.reject.iter())
+            .chain(self.repositories.entries());
+        let overridden_entries = self
+            .overlays
+            .iter()
+            .map(|overlay| &overlay.rules)
+            .chain(self.container_types.iter().map(|(_, rules)| rules))
+            .chain(
+                self.container_overrides
+                    .iter()
+                    .map(|container_override| &container_override.rules),
+            )
+            .flat_map(RuleOverrides::templated_entries);
+        let templated = base_entries
+            .chain(overridden_entries)
+            .flat_map(|entry| template::placeholders(entry))
+            .any(|placeholder| placeholder.starts_with(NAMESPACE_LABELS_PLACEHOLDER_PREFIX));
+
+        templated
+            || self
+                .overlays
+                .iter()
+                .any(|overlay| overlay.namespace_selector.is_some())
+    }
+
+    /// The settings to be used for a request about a resource of the given
+    /// kinds, made by the given user inside of the given namespace: the base
+    /// settings with all the matching overlays applied, in order. The
+    /// container overrides restricted to other namespaces are dropped
+    pub fn overlaid(
+        &self,
+        kinds: &[&str],
+        namespace: &str,
+        namespace_labels: &BTreeMap<String, String>,
+        user_info: &UserInfo,
+    ) -> Settings {
+        let mut settings = self
+            .overlays
+            .iter()
+            .filter(|overlay| overlay.matches(kinds, namespace, namespace_labels, user_info))
+            .fold(self.base(), |settings, overlay| {
+                settings.with_overlay(overlay)
+            });
+        settings
+            .container_overrides
+            .retain(|container_override| container_override.matches_namespace(namespace));
+        settings
+    }
+
+    /// Fill the placeholders of the `registries` and `repositories` entries
+    /// with the details of the request. Placeholders without a value, like a
+    /// missing label, are left untouched: such entries cannot match any image
+    pub fn for_request(&self, context: &RuleContext) -> Settings {
+        let render =
+            |entry: &String| template::render(entry, |placeholder| context.lookup(placeholder));
+        Settings {
+            registries: self.registries.map(render),
+            repositories: self.repositories.map(render),
+            container_types: self.container_types.map(render),
+            container_overrides: self
+                .container_overrides
+                .iter()
+                .map(|container_override| ContainerOverride {
+                    rules: container_override.rules.map(render),
+                    ..container_override.clone()
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The settings without overlays
+    fn base(&self) -> Settings {
+        Settings {
+            overlays: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    fn with_overlay(self, overlay: &Overlay) -> Settings {
+        let settings = self.with_rules(&overlay.rules);
+        Settings {
+            rejection_message: overlay
+                .rejection_message
+                .clone()
+                .unwrap_or(settings.rejection_message),
+            ..settings
+        }
+    }
+
+    fn with_rules(self, rules: &RuleOverrides) -> Settings {
+        Settings {
+            registries: rules.registries.clone().unwrap_or(self.registries),
+            tags: rules.tags.clone().unwrap_or(self.tags),
+            images: rules.images.clone().unwrap_or(self.images),
+            repositories: rules.repositories.clone().unwrap_or(self.repositories),
+            deprecated_registries: rules
+                .deprecated_registries
+                .clone()
+                .unwrap_or(self.deprecated_registries),
+            ..self
+        }
+    }
+
+    /// The settings to be used for the given container: the rules provided
+    /// for its kind, then the ones of the overrides matching its name,
+    /// replace the whole sections of the settings. `None` when the container
+    /// is exempted by an override. The overrides select containers only,
+    /// never image volumes
+    pub fn for_container(
+        &self,
+        container_type: ContainerType,
+        name: &str,
+    ) -> Option<Cow<'_, Settings>> {
+        let overrides: Vec<&ContainerOverride> = self
+            .container_overrides
+            .iter()
+            .filter(|container_override| {
+                container_type != ContainerType::ImageVolume
+                    && container_override.matches_container(name)
+            })
+            .collect();
+        if overrides
+            .iter()
+            .any(|container_override| container_override.exempt)
+        {
+            return None;
+        }
+
+        let rules = self.container_types.get(container_type).into_iter().chain(
+            overrides
+                .iter()
+                .map(|container_override| &container_override.rules),
+        );
+        let mut settings = Cow::Borrowed(self);
+        for rules in rules {
+            settings = Cow::Owned(settings.into_owned().with_rules(rules));
+        }
+        Some(settings)
+    }
+
+    /// Validate the settings without taking the overlays into account
+    fn validate_rules(&self) -> Result<(), String> {
         let errors = vec![
             self.registries.validate(),
             self.images.validate(),
             self.tags.validate(),
+            self.repositories.validate(),
+            self.deprecated_registries.validate(),
+            self.rejection_message.validate(),
+            self.validate_placeholders(),
+            self.validate_schedule(),
         ]
         .into_iter()
         .filter_map(Result::err)
@@ -130,6 +1058,217 @@ impl Validatable for Settings {
         if !errors.is_empty() {
             return Err(errors.join(", "));
         }
+        Ok(())
+    }
+
+    /// Ensure the `registries` and `repositories` entries use only the
+    /// placeholders that can be filled from the request
+    fn validate_placeholders(&self) -> Result<(), String> {
+        let mut unknown: Vec<&str> = self
+            .registries
+            .allow
+            .iter()
+            .chain(self.registries.reject.iter())
+            .chain(self.repositories.entries())
+            .flat_map(|entry| template::placeholders(entry))
+            .filter(|placeholder| !is_rule_placeholder(placeholder))
+            .collect();
+        unknown.sort();
+        unknown.dedup();
+
+        if !unknown.is_empty() {
+            return Err(format!(
+                "unknown placeholders {unknown:?}, only namespace, labels.<key> and namespaceLabels.<key> can be used"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Report the entries of the allow and reject lists that start after
+    /// they expire. The expired entries are not errors, they are ignored
+    /// when the requests are evaluated
+    fn validate_schedule(&self) -> Result<(), String> {
+        let errors: Vec<String> = [
+            schedule_error("registries allow", &self.registries.allow, String::clone),
+            schedule_error("registries reject", &self.registries.reject, String::clone),
+            schedule_error("tags reject", &self.tags.reject, String::clone),
+            schedule_error("images allow", &self.images.allow, ImageRef::whole),
+            schedule_error("images reject", &self.images.reject, ImageRef::whole),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if !errors.is_empty() {
+            return Err(errors.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Describe the entries of the allow and reject lists that expired before
+    /// the given day, including the ones of the overlays and of the rules
+    /// per container
+    pub fn expired_entries(&self, today: NaiveDate) -> Vec<String> {
+        let base = expired_entries(
+            Some(&self.registries),
+            Some(&self.tags),
+            Some(&self.images),
+            today,
+        );
+        let rules = self
+            .overlays
+            .iter()
+            .enumerate()
+            .map(|(index, overlay)| (format!("overlays[{index}]"), &overlay.rules))
+            .chain(
+                self.container_types
+                    .iter()
+                    .map(|(field, rules)| (format!("containerTypes.{field}"), rules)),
+            )
+            .chain(self.container_overrides.iter().enumerate().map(
+                |(index, container_override)| {
+                    (
+                        format!("containerOverrides[{index}]"),
+                        &container_override.rules,
+                    )
+                },
+            ))
+            .flat_map(|(path, rules)| {
+                expired_entries(
+                    rules.registries.as_ref(),
+                    rules.tags.as_ref(),
+                    rules.images.as_ref(),
+                    today,
+                )
+                .into_iter()
+                .map(move |expired| format!("{path}: {expired}"))
+            });
+        base.into_iter().chain(rules).collect()
+    }
+}
+
+fn schedule_error<T: Eq + Hash, K>(
+    list: &str,
+    rule_set: &RuleSet<T, K>,
+    name: impl Fn(&T) -> String,
+) -> Option<String> {
+    let misscheduled: Vec<String> = rule_set.misscheduled().map(&name).collect();
+    (!misscheduled.is_empty())
+        .then(|| format!("{list} entries {misscheduled:?} start after they expire"))
+}
+
+fn expired_entries(
+    registries: Option<&Registries>,
+    tags: Option<&Tags>,
+    images: Option<&Images>,
+    today: NaiveDate,
+) -> Vec<String> {
+    fn describe<T: Eq + Hash, K>(
+        list: &str,
+        rule_set: &RuleSet<T, K>,
+        today: NaiveDate,
+        name: impl Fn(&T) -> String,
+    ) -> Option<String> {
+        let expired: Vec<String> = rule_set.expired(today).map(&name).collect();
+        (!expired.is_empty()).then(|| format!("{list} entries {expired:?} expired"))
+    }
+
+    [
+        registries.and_then(|registries| {
+            describe("registries allow", &registries.allow, today, String::clone)
+        }),
+        registries.and_then(|registries| {
+            describe(
+                "registries reject",
+                &registries.reject,
+                today,
+                String::clone,
+            )
+        }),
+        tags.and_then(|tags| describe("tags reject", &tags.reject, today, String::clone)),
+        images.and_then(|images| describe("images allow", &images.allow, today, ImageRef::whole)),
+        images.and_then(|images| describe("images reject", &images.reject, today, ImageRef::whole)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+impl Validatable for Settings {
+    fn validate(&self) -> Result<(), String> {
+        let base = self.base();
+        let overlays = self.overlays.iter().enumerate().map(|(index, overlay)| {
+            // the rules of an overlay are validated once merged with the base
+            // ones, e.g. an overlay providing a registries allow list replaces
+            // the registries reject list of the base settings
+            overlay
+                .validate()
+                .and_then(|_| base.clone().with_overlay(overlay).validate_rules())
+                .map_err(|error| format!("overlays[{index}]: {error}"))
+        });
+        // like the overlays, the rules of a kind of container are validated
+        // once merged with the base ones
+        let container_types = self.container_types.iter().map(|(field, rules)| {
+            base.clone()
+                .with_rules(rules)
+                .validate_rules()
+                .map_err(|error| format!("containerTypes.{field}: {error}"))
+        });
+        let container_overrides =
+            self.container_overrides
+                .iter()
+                .enumerate()
+                .map(|(index, container_override)| {
+                    container_override
+                        .validate()
+                        .and_then(|_| {
+                            base.clone()
+                                .with_rules(&container_override.rules)
+                                .validate_rules()
+                        })
+                        .map_err(|error| format!("containerOverrides[{index}]: {error}"))
+                });
+        let exemptions = self
+            .exemptions
+            .iter()
+            .enumerate()
+            .map(|(index, exemption)| {
+                exemption
+                    .validate()
+                    .map_err(|error| format!("exemptions[{index}]: {error}"))
+            });
+        let custom_resources =
+            self.custom_resources
+                .iter()
+                .enumerate()
+                .map(|(index, custom_resource)| {
+                    custom_resource
+                        .validate()
+                        .map_err(|error| format!("customResources[{index}]: {error}"))
+                });
+        let break_glass = self
+            .break_glass
+            .validate()
+            .map_err(|error| format!("breakGlass: {error}"));
+        let errors = std::iter::once(base.validate_rules())
+            .chain(overlays)
+            .chain(container_types)
+            .chain(container_overrides)
+            .chain(exemptions)
+            .chain(custom_resources)
+            .chain(std::iter::once(break_glass))
+            .filter_map(Result::err)
+            .collect::<Vec<String>>();
+
+        if !errors.is_empty() {
+            return Err(errors.join(", "));
+        }
+
+        // settings that were valid yesterday must still be loaded today, the
+        // expired entries are only reported
+        for expired in self.expired_entries(Utc::now().date_naive()) {
+            warn!(LOG_DRAIN, "{expired}, they are ignored");
+        }
 
         Ok(())
     }
@@ -138,6 +1277,7 @@ impl Validatable for Settings {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_helpers::user_info;
     use rstest::*;
 
     #[rstest]
@@ -157,6 +1297,7 @@ mod tests {
         let registries = Registries {
             allow: allow.into_iter().collect(),
             reject: reject.into_iter().collect(),
+            ..Registries::default()
         };
 
         let result = registries.validate();
@@ -190,6 +1331,7 @@ mod tests {
                 .iter()
                 .map(|image| ImageRef(Reference::from_str(image).unwrap()))
                 .collect(),
+            ..Images::default()
         };
 
         let result = images.validate();
@@ -239,6 +1381,7 @@ mod tests {
     fn validate_tags(#[case] tags: Vec<String>, #[case] is_valid: bool) {
         let tags = Tags {
             reject: tags.into_iter().collect(),
+            ..Tags::default()
         };
 
         let result = tags.validate();
@@ -249,6 +1392,676 @@ mod tests {
         }
     }
 
+    #[rstest]
+    #[case::namespaces_only(
+        r#"{"docker.io": {"namespaces": ["library"]}, "ghcr.io": {"namespaces": ["my-org/team"]}}"#,
+        true
+    )]
+    #[case::max_depth_only(r#"{"ghcr.io": {"maxDepth": 2}}"#, true)]
+    #[case::namespaces_and_max_depth(
+        r#"{"ghcr.io": {"namespaces": ["my-org"], "maxDepth": 2}}"#,
+        true
+    )]
+    #[case::empty_namespace(r#"{"ghcr.io": {"namespaces": [""]}}"#, false)]
+    #[case::namespace_with_trailing_slash(r#"{"ghcr.io": {"namespaces": ["my-org/"]}}"#, false)]
+    #[case::zero_max_depth(r#"{"ghcr.io": {"maxDepth": 0}}"#, false)]
+    #[case::namespace_deeper_than_max_depth(
+        r#"{"ghcr.io": {"namespaces": ["my-org/team"], "maxDepth": 2}}"#,
+        false
+    )]
+    fn validate_repositories(#[case] input: &str, #[case] is_valid: bool) {
+        let repositories: Repositories = serde_json::from_str(input).unwrap();
+
+        let result = repositories.validate();
+        if is_valid {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            assert!(result.is_err(), "was supposed to be invalid");
+        }
+    }
+
+    #[rstest]
+    #[case::defaults(r#"{}"#, true)]
+    #[case::valid_entries(
+        r#"{
+            "action": "deny",
+            "registries": [
+                {"registry": "old-registry.corp.com", "replacement": "registry.corp.com"},
+                {"registry": "k8s.gcr.io", "replacement": "registry.k8s.io", "action": "warn"}
+            ]
+        }"#,
+        true
+    )]
+    #[case::missing_replacement(
+        r#"{"registries": [{"registry": "old-registry.corp.com", "replacement": ""}]}"#,
+        false
+    )]
+    #[case::trailing_slash(
+        r#"{"registries": [{"registry": "gcr.io/my-project/", "replacement": "registry.corp.com"}]}"#,
+        false
+    )]
+    fn validate_deprecated_registries(#[case] input: &str, #[case] is_valid: bool) {
+        let deprecated_registries: DeprecatedRegistries = serde_json::from_str(input).unwrap();
+
+        let result = deprecated_registries.validate();
+        if is_valid {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            assert!(result.is_err(), "was supposed to be invalid");
+        }
+    }
+
+    #[rstest]
+    #[case::defaults(r#"{}"#, true)]
+    #[case::template_and_code(
+        r#"{"template": "{{container}} uses {{ image }} ({{rule}}), see https://wiki.corp.com/images", "code": 403}"#,
+        true
+    )]
+    #[case::empty_template(r#"{"template": " "}"#, false)]
+    #[case::unknown_placeholder(r#"{"template": "{{container}} uses {{pod}}"}"#, false)]
+    #[case::not_an_error_code(r#"{"code": 200}"#, false)]
+    fn validate_rejection_message(#[case] input: &str, #[case] is_valid: bool) {
+        let rejection_message: RejectionMessage = serde_json::from_str(input).unwrap();
+
+        let result = rejection_message.validate();
+        if is_valid {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            assert!(result.is_err(), "was supposed to be invalid");
+        }
+    }
+
+    #[rstest]
+    #[case::no_expiration(
+        r#"{"registries": {"allow": ["quay.io", {"registry": "ghcr.io", "owner": "team-a"}]}}"#,
+        true
+    )]
+    #[case::starts_after_expiration(
+        r#"{"tags": {"reject": [{"tag": "edge", "starts": "2026-06-01", "expires": "2026-05-31"}]}}"#,
+        false
+    )]
+    fn validate_schedule(#[case] input: &str, #[case] is_valid: bool) {
+        let settings: Settings = serde_json::from_str(input).unwrap();
+
+        let result = settings.validate_schedule();
+        if is_valid {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            assert!(result.is_err(), "was supposed to be invalid");
+        }
+    }
+
+    #[rstest]
+    #[case::not_expired_yet(
+        r#"{"tags": {"reject": [{"tag": "edge", "expires": "2026-03-01"}]}}"#,
+        vec![],
+        vec!["edge"]
+    )]
+    #[case::expired_registry(
+        r#"{"registries": {"reject": ["docker.io", {"registry": "quay.io", "expires": "2026-02-28"}]}}"#,
+        vec![r#"registries reject entries ["quay.io"] expired"#],
+        vec!["docker.io"]
+    )]
+    #[case::expired_image(
+        r#"{"images": {"allow": [{"image": "nginx:1.25.3", "ticket": "OPS-1234", "expires": "2026-01-01"}]}}"#,
+        vec![r#"images allow entries ["docker.io/library/nginx:1.25.3"] expired"#],
+        vec![]
+    )]
+    fn expired_entries_reported(
+        #[case] input: &str,
+        #[case] expected_expired: Vec<&str>,
+        #[case] expected_active: Vec<&str>,
+    ) {
+        let settings: Settings = serde_json::from_str(input).unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+
+        assert_eq!(settings.expired_entries(today), expected_expired);
+        let mut active: Vec<String> = settings
+            .registries
+            .reject
+            .active(today)
+            .cloned()
+            .chain(settings.tags.reject.active(today).cloned())
+            .chain(settings.images.allow.active(today).map(ImageRef::whole))
+            .collect();
+        active.sort();
+        assert_eq!(active, expected_active);
+    }
+
+    #[test]
+    fn settings_with_expired_entries_still_validate() {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com", {"registry": "docker.io", "expires": "2020-01-01"}]},
+                "overlays": [{"namespaces": ["legacy"], "tags": {"reject": [{"tag": "edge", "expires": "2020-01-01"}]}}]
+            }"#,
+        )
+        .unwrap();
+
+        assert!(settings.validate().is_ok());
+        let today = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        assert_eq!(
+            settings.expired_entries(today),
+            vec![
+                r#"registries allow entries ["docker.io"] expired"#.to_string(),
+                r#"overlays[0]: tags reject entries ["edge"] expired"#.to_string(),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::no_matching_overlay("default", vec!["registry.corp.com"], vec![])]
+    #[case::exact_name("legacy", vec!["registry.corp.com", "docker.io"], vec![])]
+    #[case::glob("sandbox-team-a", vec!["docker.io"], vec!["latest"])]
+    #[case::overlays_applied_in_order("sandbox-legacy", vec!["registry.corp.com", "docker.io"], vec!["latest"])]
+    fn settings_for_namespace(
+        #[case] namespace: &str,
+        #[case] expected_allowed_registries: Vec<&str>,
+        #[case] expected_rejected_tags: Vec<&str>,
+    ) {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com"]},
+                "overlays": [
+                    {
+                        "namespaces": ["sandbox-*"],
+                        "registries": {"allow": ["docker.io"]},
+                        "tags": {"reject": ["latest"]}
+                    },
+                    {
+                        "namespaces": ["legacy", "*-legacy"],
+                        "registries": {"allow": ["registry.corp.com", "docker.io"]}
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let effective =
+            settings.overlaid(&["Pod"], namespace, &BTreeMap::new(), &UserInfo::default());
+
+        let mut allowed_registries: Vec<&String> = effective.registries.allow.iter().collect();
+        allowed_registries.sort();
+        let mut expected_allowed_registries = expected_allowed_registries;
+        expected_allowed_registries.sort();
+        assert_eq!(allowed_registries, expected_allowed_registries);
+        let rejected_tags: Vec<&String> = effective.tags.reject.iter().collect();
+        assert_eq!(rejected_tags, expected_rejected_tags);
+        assert!(effective.overlays.is_empty());
+    }
+
+    #[rstest]
+    #[case::no_matching_labels("team-a", &[("security-tier", "baseline")], vec!["registry.corp.com", "docker.io"])]
+    #[case::matching_labels("team-a", &[("security-tier", "restricted")], vec!["registry.corp.com"])]
+    #[case::matching_labels_other_namespace_name("sandbox-team-a", &[("security-tier", "restricted")], vec!["registry.corp.com", "docker.io"])]
+    fn settings_for_namespace_labels(
+        #[case] namespace: &str,
+        #[case] labels: &[(&str, &str)],
+        #[case] expected_allowed_registries: Vec<&str>,
+    ) {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com", "docker.io"]},
+                "overlays": [
+                    {
+                        "namespaceSelector": {"matchLabels": {"security-tier": "restricted"}},
+                        "registries": {"allow": ["registry.corp.com"]}
+                    },
+                    {
+                        "namespaces": ["sandbox-*"],
+                        "namespaceSelector": {"matchExpressions": [{"key": "security-tier", "operator": "Exists"}]},
+                        "registries": {"allow": ["registry.corp.com", "docker.io"]}
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let labels: BTreeMap<String, String> = labels
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        assert!(settings.needs_namespace_labels());
+        let effective = settings.overlaid(&["Pod"], namespace, &labels, &UserInfo::default());
+
+        let mut allowed_registries: Vec<&String> = effective.registries.allow.iter().collect();
+        allowed_registries.sort();
+        let mut expected_allowed_registries = expected_allowed_registries;
+        expected_allowed_registries.sort();
+        assert_eq!(allowed_registries, expected_allowed_registries);
+    }
+
+    #[rstest]
+    #[case::namespace_and_labels(
+        "team-a",
+        &[("team", "payments")],
+        &[("team", "checkout")],
+        vec!["payments", "team-a", "teams/checkout"]
+    )]
+    #[case::missing_labels(
+        "team-a",
+        &[],
+        &[],
+        vec!["team-a", "{{labels.team}}", "teams/{{namespaceLabels.team}}"]
+    )]
+    #[case::cluster_scoped(
+        "",
+        &[("team", "payments")],
+        &[],
+        vec!["payments", "{{namespace}}", "teams/{{namespaceLabels.team}}"]
+    )]
+    fn settings_for_request(
+        #[case] namespace: &str,
+        #[case] labels: &[(&str, &str)],
+        #[case] namespace_labels: &[(&str, &str)],
+        #[case] expected_namespaces: Vec<&str>,
+    ) {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com"]},
+                "repositories": {"registry.corp.com": {"namespaces": ["{{namespace}}", "{{labels.team}}", "teams/{{namespaceLabels.team}}"]}}
+            }"#,
+        )
+        .unwrap();
+        let to_map = |labels: &[(&str, &str)]| -> BTreeMap<String, String> {
+            labels
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+
+        let effective = settings.for_request(&RuleContext {
+            namespace,
+            labels: &to_map(labels),
+            namespace_labels: &to_map(namespace_labels),
+        });
+
+        let constraints = effective.repositories.get("registry.corp.com").unwrap();
+        let mut namespaces: Vec<&String> = constraints.namespaces.iter().collect();
+        namespaces.sort();
+        let mut expected_namespaces = expected_namespaces;
+        expected_namespaces.sort();
+        assert_eq!(namespaces, expected_namespaces);
+    }
+
+    #[rstest]
+    #[case::valid_overlay(
+        r#"{"registries": {"reject": ["docker.io"]}, "overlays": [{"namespaces": ["sandbox-*"], "registries": {"allow": ["docker.io"]}}]}"#,
+        true
+    )]
+    #[case::missing_namespaces(
+        r#"{"overlays": [{"registries": {"allow": ["docker.io"]}}]}"#,
+        false
+    )]
+    #[case::empty_namespace(
+        r#"{"overlays": [{"namespaces": [""], "registries": {"allow": ["docker.io"]}}]}"#,
+        false
+    )]
+    #[case::namespace_selector_only(
+        r#"{"overlays": [{"namespaceSelector": {"matchLabels": {"security-tier": "restricted"}}, "registries": {"allow": ["docker.io"]}}]}"#,
+        true
+    )]
+    #[case::invalid_namespace_selector(
+        r#"{"overlays": [{"namespaceSelector": {"matchExpressions": [{"key": "security-tier", "operator": "In"}]}, "registries": {"allow": ["docker.io"]}}]}"#,
+        false
+    )]
+    #[case::unknown_placeholder_in_overlay(
+        r#"{"overlays": [{"namespaces": ["team-*"], "registries": {"allow": ["{{labels}}.registry.corp.com"]}}]}"#,
+        false
+    )]
+    #[case::kinds_only(
+        r#"{"overlays": [{"kinds": ["Job", "CronJob"], "registries": {"allow": ["registry.corp.com"]}}]}"#,
+        true
+    )]
+    #[case::empty_kind(
+        r#"{"overlays": [{"kinds": [""], "registries": {"allow": ["registry.corp.com"]}}]}"#,
+        false
+    )]
+    #[case::invalid_overlay_rules(
+        r#"{"overlays": [{"namespaces": ["sandbox-*"], "registries": {"allow": ["docker.io"], "reject": ["quay.io"]}}]}"#,
+        false
+    )]
+    fn validate_overlays(#[case] input: &str, #[case] is_valid: bool) {
+        let settings: Settings = serde_json::from_str(input).unwrap();
+
+        let result = settings.validate();
+        if is_valid {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            assert!(result.is_err(), "was supposed to be invalid");
+        }
+    }
+
+    #[rstest]
+    #[case::templated_entries(
+        r#"{"registries": {"allow": ["registry.corp.com"]}, "repositories": {"registry.corp.com": {"namespaces": ["{{namespace}}", "teams/{{labels.team}}"]}}}"#,
+        true
+    )]
+    #[case::unknown_placeholder(
+        r#"{"repositories": {"registry.corp.com": {"namespaces": ["{{annotations.team}}"]}}}"#,
+        false
+    )]
+    #[case::namespace_labels(
+        r#"{"registries": {"allow": ["registry.corp.com"]}, "repositories": {"registry.corp.com": {"namespaces": ["teams/{{namespaceLabels.team}}"]}}}"#,
+        true
+    )]
+    #[case::empty_label_key(
+        r#"{"registries": {"allow": ["{{labels.}}.registry.corp.com"]}}"#,
+        false
+    )]
+    #[case::empty_namespace_label_key(
+        r#"{"registries": {"allow": ["{{namespaceLabels.}}.registry.corp.com"]}}"#,
+        false
+    )]
+    fn validate_placeholders(#[case] input: &str, #[case] is_valid: bool) {
+        let settings: Settings = serde_json::from_str(input).unwrap();
+
+        let result = settings.validate();
+        if is_valid {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            assert!(result.is_err(), "was supposed to be invalid");
+        }
+    }
+
+    #[rstest]
+    #[case::no_placeholder(
+        r#"{"registries": {"allow": ["registry.corp.com/{{labels.team}}"]}}"#,
+        false
+    )]
+    #[case::namespace_selector(
+        r#"{"overlays": [{"namespaceSelector": {"matchLabels": {"security-tier": "restricted"}}, "registries": {"allow": ["docker.io"]}}]}"#,
+        true
+    )]
+    #[case::base_placeholder(
+        r#"{"registries": {"allow": ["registry.corp.com"]}, "repositories": {"registry.corp.com": {"namespaces": ["{{namespaceLabels.team}}"]}}}"#,
+        true
+    )]
+    #[case::overlay_placeholder(
+        r#"{"overlays": [{"namespaces": ["team-*"], "registries": {"allow": ["{{namespaceLabels.team}}.registry.corp.com"]}}]}"#,
+        true
+    )]
+    #[case::container_type_placeholder(
+        r#"{"containerTypes": {"initContainers": {"registries": {"allow": ["{{namespaceLabels.team}}.registry.corp.com"]}}}}"#,
+        true
+    )]
+    fn namespace_labels_needed(#[case] input: &str, #[case] expected: bool) {
+        let settings: Settings = serde_json::from_str(input).unwrap();
+
+        assert_eq!(settings.needs_namespace_labels(), expected);
+    }
+    fn object_meta(labels: &[(&str, &str)], annotations: &[(&str, &str)]) -> ObjectMeta {
+        let to_map = |entries: &[(&str, &str)]| {
+            Some(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+            )
+        };
+        ObjectMeta {
+            labels: to_map(labels),
+            annotations: to_map(annotations),
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case::no_matching_metadata(
+        object_meta(&[("app", "web")], &[]),
+        object_meta(&[], &[]),
+        None
+    )]
+    #[case::labels_of_the_resource(
+        object_meta(&[("app.kubernetes.io/part-of", "vendor-operator")], &[]),
+        object_meta(&[], &[]),
+        Some("vendor-operator")
+    )]
+    #[case::annotations_of_the_template(
+        object_meta(&[], &[]),
+        object_meta(&[], &[("vendor.example.com/managed", "true")]),
+        Some("vendor-managed")
+    )]
+    #[case::selectors_satisfied_by_different_metadata(
+        object_meta(&[("team", "platform")], &[]),
+        object_meta(&[], &[("vendor.example.com/reviewed", "true")]),
+        None
+    )]
+    #[case::all_selectors_satisfied(
+        object_meta(&[("team", "platform")], &[("vendor.example.com/reviewed", "true")]),
+        object_meta(&[], &[]),
+        Some("platform-reviewed")
+    )]
+    fn exemption_for_metadata(
+        #[case] metadata: ObjectMeta,
+        #[case] template_metadata: ObjectMeta,
+        #[case] expected_exemption: Option<&str>,
+    ) {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "exemptions": [
+                    {"name": "vendor-operator", "labelSelector": {"matchLabels": {"app.kubernetes.io/part-of": "vendor-operator"}}},
+                    {"name": "vendor-managed", "annotationSelector": {"matchExpressions": [{"key": "vendor.example.com/managed", "operator": "Exists"}]}},
+                    {
+                        "name": "platform-reviewed",
+                        "labelSelector": {"matchLabels": {"team": "platform"}},
+                        "annotationSelector": {"matchLabels": {"vendor.example.com/reviewed": "true"}}
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let exemption = settings.exemption(&[&metadata, &template_metadata], &UserInfo::default());
+        assert_eq!(
+            exemption.map(|exemption| exemption.name.as_str()),
+            expected_exemption
+        );
+    }
+
+    #[rstest]
+    #[case::username(user_info("alice", &[]), true)]
+    #[case::group(user_info("bob", &["system:authenticated", "cluster-admins"]), true)]
+    #[case::service_account(
+        user_info("system:serviceaccount:argocd:argocd-application-controller", &[]),
+        true
+    )]
+    #[case::service_account_of_another_namespace(
+        user_info("system:serviceaccount:default:argocd-application-controller", &[]),
+        false
+    )]
+    #[case::not_listed(user_info("bob", &["system:authenticated", "developers"]), false)]
+    fn subjects_match_user(#[case] user_info: UserInfo, #[case] expected: bool) {
+        let subjects: Subjects = serde_json::from_str(
+            r#"{"users": ["alice"], "groups": ["cluster-admins"], "serviceAccounts": ["argocd/*"]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(subjects.matches(&user_info), expected);
+    }
+
+    #[rstest]
+    #[case::developer(user_info("bob", &["developers"]), vec!["registry.corp.com"])]
+    #[case::gitops_controller(
+        user_info("system:serviceaccount:argocd:argocd-application-controller", &[]),
+        vec!["registry.corp.com", "docker.io"]
+    )]
+    fn settings_for_user(
+        #[case] user_info: UserInfo,
+        #[case] expected_allowed_registries: Vec<&str>,
+    ) {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com"]},
+                "overlays": [
+                    {"serviceAccounts": ["argocd/*"], "registries": {"allow": ["registry.corp.com", "docker.io"]}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let effective = settings.overlaid(&["Pod"], "default", &BTreeMap::new(), &user_info);
+
+        let mut allowed_registries: Vec<&String> = effective.registries.allow.iter().collect();
+        allowed_registries.sort();
+        let mut expected_allowed_registries = expected_allowed_registries;
+        expected_allowed_registries.sort();
+        assert_eq!(allowed_registries, expected_allowed_registries);
+    }
+
+    #[rstest]
+    #[case::valid_exemption(
+        r#"{"exemptions": [{"name": "vendor-operator", "labelSelector": {"matchLabels": {"app": "vendor"}}}]}"#,
+        true
+    )]
+    #[case::missing_name(
+        r#"{"exemptions": [{"labelSelector": {"matchLabels": {"app": "vendor"}}}]}"#,
+        false
+    )]
+    #[case::exemption_by_user(
+        r#"{"exemptions": [{"name": "gitops", "serviceAccounts": ["argocd/argocd-application-controller"], "groups": ["cluster-admins"]}]}"#,
+        true
+    )]
+    #[case::invalid_service_account(
+        r#"{"exemptions": [{"name": "gitops", "serviceAccounts": ["argocd-application-controller"]}]}"#,
+        false
+    )]
+    #[case::empty_group(r#"{"exemptions": [{"name": "admins", "groups": [""]}]}"#, false)]
+    #[case::break_glass(r#"{"breakGlass": {"groups": ["sre"]}}"#, true)]
+    #[case::invalid_break_glass_service_account(
+        r#"{"breakGlass": {"serviceAccounts": ["sre/on-call/bot"]}}"#,
+        false
+    )]
+    #[case::overlay_by_user(
+        r#"{"overlays": [{"groups": ["cluster-admins"], "registries": {"allow": ["docker.io"]}}]}"#,
+        true
+    )]
+    #[case::missing_selectors(r#"{"exemptions": [{"name": "vendor-operator"}]}"#, false)]
+    #[case::invalid_selector(
+        r#"{"exemptions": [{"name": "vendor-operator", "annotationSelector": {"matchExpressions": [{"key": "vendor", "operator": "Exists", "values": ["true"]}]}}]}"#,
+        false
+    )]
+    fn validate_exemptions(#[case] input: &str, #[case] is_valid: bool) {
+        let settings: Settings = serde_json::from_str(input).unwrap();
+
+        let result = settings.validate();
+        if is_valid {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            assert!(result.is_err(), "was supposed to be invalid");
+        }
+    }
+
+    #[rstest]
+    #[case::image_paths(
+        r#"{"customResources": [{"group": "tekton.dev", "kind": "Task", "images": ["spec.steps[*].image", "spec.sidecars[*].image"]}]}"#,
+        true
+    )]
+    #[case::pod_spec_paths(
+        r#"{"customResources": [{"group": "example.com", "kind": "Runner", "podSpecs": ["spec.template.spec"]}]}"#,
+        true
+    )]
+    #[case::missing_kind(
+        r#"{"customResources": [{"group": "tekton.dev", "images": ["spec.steps[*].image"]}]}"#,
+        false
+    )]
+    #[case::missing_paths(
+        r#"{"customResources": [{"group": "tekton.dev", "kind": "Task"}]}"#,
+        false
+    )]
+    #[case::invalid_path(
+        r#"{"customResources": [{"group": "tekton.dev", "kind": "Task", "images": ["spec.steps[*.image"]}]}"#,
+        false
+    )]
+    fn validate_custom_resources(#[case] input: &str, #[case] is_valid: bool) {
+        let result = serde_json::from_str::<Settings>(input)
+            .map_err(|error| error.to_string())
+            .and_then(|settings| settings.validate());
+        assert_eq!(result.is_ok(), is_valid, "unexpected result {result:?}");
+    }
+
+    #[rstest]
+    #[case::override_for_all_namespaces("default", "istio-proxy", None)]
+    #[case::override_restricted_to_the_namespace("legacy", "linkerd-proxy", Some(vec!["cr.l5d.io"]))]
+    #[case::override_restricted_to_other_namespaces("default", "linkerd-proxy", Some(vec!["registry.corp.com"]))]
+    #[case::container_type_rules(
+        "default",
+        "app",
+        Some(vec!["registry.corp.com", "docker.io"])
+    )]
+    fn settings_for_container(
+        #[case] namespace: &str,
+        #[case] container: &str,
+        #[case] expected_allowed_registries: Option<Vec<&str>>,
+    ) {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com"]},
+                "containerTypes": {"containers": {"registries": {"allow": ["registry.corp.com", "docker.io"]}}},
+                "containerOverrides": [
+                    {"names": ["istio-proxy"], "exempt": true},
+                    {"names": ["linkerd-*"], "namespaces": ["legacy"], "registries": {"allow": ["cr.l5d.io"]}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let settings =
+            settings.overlaid(&["Pod"], namespace, &BTreeMap::new(), &UserInfo::default());
+        let container_type = if container == "app" {
+            ContainerType::Container
+        } else {
+            ContainerType::InitContainer
+        };
+        let allowed_registries =
+            settings
+                .for_container(container_type, container)
+                .map(|settings| {
+                    let mut allowed_registries: Vec<String> =
+                        settings.registries.allow.iter().cloned().collect();
+                    allowed_registries.sort();
+                    allowed_registries
+                });
+        let expected_allowed_registries = expected_allowed_registries.map(|registries| {
+            let mut registries: Vec<String> = registries.into_iter().map(String::from).collect();
+            registries.sort();
+            registries
+        });
+        assert_eq!(allowed_registries, expected_allowed_registries);
+    }
+
+    #[rstest]
+    #[case::container_types(
+        r#"{"registries": {"reject": ["docker.io"]}, "containerTypes": {"ephemeralContainers": {"registries": {"allow": ["docker.io"]}}}}"#,
+        true
+    )]
+    #[case::container_overrides(
+        r#"{"containerOverrides": [{"names": ["istio-proxy"], "namespaces": ["mesh-*"], "exempt": true}]}"#,
+        true
+    )]
+    #[case::container_override_without_names(
+        r#"{"containerOverrides": [{"namespaces": ["mesh-*"], "exempt": true}]}"#,
+        false
+    )]
+    #[case::invalid_container_override_rules(
+        r#"{"registries": {"reject": ["docker.io"]}, "containerOverrides": [{"names": ["linkerd-proxy"], "registries": {"allow": ["cr.l5d.io"], "reject": ["docker.io"]}}]}"#,
+        false
+    )]
+    #[case::invalid_container_type_rules(
+        r#"{"containerTypes": {"sidecarContainers": {"tags": {"reject": ["not a tag"]}}}}"#,
+        false
+    )]
+    fn validate_container_rules(#[case] input: &str, #[case] is_valid: bool) {
+        let settings: Settings = serde_json::from_str(input).unwrap();
+
+        let result = settings.validate();
+        if is_valid {
+            assert!(result.is_ok(), "{result:?}");
+        } else {
+            assert!(result.is_err(), "was supposed to be invalid");
+        }
+    }
+
     #[rstest]
     #[case::empty_settings(Settings::default(), true)]
     #[case::valid_settings(
@@ -259,11 +2072,13 @@ mod tests {
             },
             tags: Tags {
                 reject: vec!["latest".to_string()].into_iter().collect(),
+                ..Tags::default()
             },
             images: Images {
                 reject: vec!["busybox".to_string()].into_iter().map(|image| Reference::from_str(&image).unwrap().into()).collect(),
                 ..Images::default()
             },
+            ..Settings::default()
         },
         true
     )]
@@ -272,14 +2087,17 @@ mod tests {
             registries: Registries {
                 allow: vec!["registry.com".to_string()].into_iter().collect(),
                 reject: vec!["registry2.com".to_string()].into_iter().collect(),
+                ..Registries::default()
             },
             tags: Tags {
                 reject: vec!["latest".to_string()].into_iter().collect(),
+                ..Tags::default()
             },
             images: Images {
                 reject: vec!["busybox".to_string()].into_iter().map(|image| Reference::from_str(&image).unwrap().into()).collect(),
                 ..Images::default()
             },
+            ..Settings::default()
         },
         false
     )]
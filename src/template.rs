@@ -0,0 +1,76 @@
+/// Return the names of the `{{placeholder}}` found inside of the template,
+/// in order of appearance
+pub(crate) fn placeholders(template: &str) -> Vec<&str> {
+    let mut placeholders = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after_start = &rest[start + 2..];
+        match after_start.find("}}") {
+            Some(end) => {
+                placeholders.push(after_start[..end].trim());
+                rest = &after_start[end + 2..];
+            }
+            None => break,
+        }
+    }
+    placeholders
+}
+
+/// Replace the `{{placeholder}}` found inside of the template with the value
+/// returned by `lookup`. Placeholders unknown to `lookup` are left untouched
+pub(crate) fn render<F>(template: &str, lookup: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after_start = &rest[start + 2..];
+        let end = match after_start.find("}}") {
+            Some(end) => end,
+            None => break,
+        };
+        rendered.push_str(&rest[..start]);
+        match lookup(after_start[..end].trim()) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after_start[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::no_placeholders("no placeholders", Vec::new())]
+    #[case::placeholders(
+        "{{container}} uses {{ image }}, see {{url}}",
+        vec!["container", "image", "url"]
+    )]
+    #[case::unterminated_placeholder("{{container}} uses {{image", vec!["container"])]
+    fn find_placeholders(#[case] template: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(placeholders(template), expected);
+    }
+
+    #[rstest]
+    #[case::no_placeholders("no placeholders", "no placeholders")]
+    #[case::known_placeholders(
+        "{{container}} uses {{ image }}",
+        "nginx uses docker.io/library/nginx:latest"
+    )]
+    #[case::unknown_placeholder("{{container}} uses {{unknown}}", "nginx uses {{unknown}}")]
+    #[case::unterminated_placeholder("{{container}} uses {{image", "nginx uses {{image")]
+    fn render_template(#[case] template: &str, #[case] expected: &str) {
+        let rendered = render(template, |placeholder| match placeholder {
+            "container" => Some("nginx".to_string()),
+            "image" => Some("docker.io/library/nginx:latest".to_string()),
+            _ => None,
+        });
+        assert_eq!(rendered, expected);
+    }
+}
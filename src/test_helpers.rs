@@ -0,0 +1,10 @@
+use kubewarden_policy_sdk::request::UserInfo;
+
+/// The details of a user making a request, with the given groups
+pub(crate) fn user_info(username: &str, groups: &[&str]) -> UserInfo {
+    UserInfo {
+        username: username.to_string(),
+        groups: groups.iter().map(|group| group.to_string()).collect(),
+        ..Default::default()
+    }
+}
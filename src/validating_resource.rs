@@ -1,57 +1,157 @@
-use k8s_openapi::api::{
-    apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet},
-    batch::v1::{CronJob, Job},
-    core::v1::{Pod, PodSpec, ReplicationController},
+use k8s_openapi::{
+    api::{
+        apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet},
+        batch::v1::{CronJob, Job},
+        core::v1::{Pod, PodSpec, PodTemplate, ReplicationController},
+    },
+    apimachinery::pkg::apis::meta::v1::ObjectMeta,
 };
 
 /// Represents all resources that can be validated with this policy
 pub trait ValidatingResource {
+    /// Path of the Pod specification inside of the resource
+    const SPEC_PATH: &'static str;
+
     fn spec(&self) -> Option<PodSpec>;
+
+    /// Metadata of the resource itself
+    fn metadata(&self) -> &ObjectMeta;
+
+    /// Metadata of the Pod template, for the resources creating Pods
+    fn template_metadata(&self) -> Option<&ObjectMeta>;
 }
 
 impl ValidatingResource for Pod {
+    const SPEC_PATH: &'static str = "spec";
+
     fn spec(&self) -> Option<PodSpec> {
         self.spec.clone()
     }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn template_metadata(&self) -> Option<&ObjectMeta> {
+        None
+    }
+}
+
+impl ValidatingResource for PodTemplate {
+    const SPEC_PATH: &'static str = "template.spec";
+
+    fn spec(&self) -> Option<PodSpec> {
+        self.template.as_ref()?.spec.clone()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn template_metadata(&self) -> Option<&ObjectMeta> {
+        self.template.as_ref()?.metadata.as_ref()
+    }
 }
 
 impl ValidatingResource for Deployment {
+    const SPEC_PATH: &'static str = "spec.template.spec";
+
     fn spec(&self) -> Option<PodSpec> {
         self.spec.as_ref()?.template.spec.clone()
     }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn template_metadata(&self) -> Option<&ObjectMeta> {
+        self.spec.as_ref()?.template.metadata.as_ref()
+    }
 }
 
 impl ValidatingResource for ReplicaSet {
+    const SPEC_PATH: &'static str = "spec.template.spec";
+
     fn spec(&self) -> Option<PodSpec> {
         self.spec.as_ref()?.template.as_ref()?.spec.clone()
     }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn template_metadata(&self) -> Option<&ObjectMeta> {
+        self.spec.as_ref()?.template.as_ref()?.metadata.as_ref()
+    }
 }
 
 impl ValidatingResource for StatefulSet {
+    const SPEC_PATH: &'static str = "spec.template.spec";
+
     fn spec(&self) -> Option<PodSpec> {
         self.spec.as_ref()?.template.spec.clone()
     }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn template_metadata(&self) -> Option<&ObjectMeta> {
+        self.spec.as_ref()?.template.metadata.as_ref()
+    }
 }
 
 impl ValidatingResource for DaemonSet {
+    const SPEC_PATH: &'static str = "spec.template.spec";
+
     fn spec(&self) -> Option<PodSpec> {
         self.spec.as_ref()?.template.spec.clone()
     }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn template_metadata(&self) -> Option<&ObjectMeta> {
+        self.spec.as_ref()?.template.metadata.as_ref()
+    }
 }
 
 impl ValidatingResource for ReplicationController {
+    const SPEC_PATH: &'static str = "spec.template.spec";
+
     fn spec(&self) -> Option<PodSpec> {
         self.spec.as_ref()?.template.as_ref()?.spec.clone()
     }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn template_metadata(&self) -> Option<&ObjectMeta> {
+        self.spec.as_ref()?.template.as_ref()?.metadata.as_ref()
+    }
 }
 
 impl ValidatingResource for Job {
+    const SPEC_PATH: &'static str = "spec.template.spec";
+
     fn spec(&self) -> Option<PodSpec> {
         self.spec.as_ref()?.template.spec.clone()
     }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn template_metadata(&self) -> Option<&ObjectMeta> {
+        self.spec.as_ref()?.template.metadata.as_ref()
+    }
 }
 
 impl ValidatingResource for CronJob {
+    const SPEC_PATH: &'static str = "spec.jobTemplate.spec.template.spec";
+
     fn spec(&self) -> Option<PodSpec> {
         self.spec
             .as_ref()?
@@ -62,4 +162,19 @@ impl ValidatingResource for CronJob {
             .spec
             .clone()
     }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn template_metadata(&self) -> Option<&ObjectMeta> {
+        self.spec
+            .as_ref()?
+            .job_template
+            .spec
+            .as_ref()?
+            .template
+            .metadata
+            .as_ref()
+    }
 }
@@ -1,92 +1,514 @@
-use std::collections::HashSet;
-use std::str::FromStr;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use chrono::NaiveDate;
 
 use crate::{
-    settings::{ImageRef, Settings},
-    validation_result::{PodRejectionReasons, PodSpecValidationResult},
+    deprecated_registries::find_deprecated_registry,
+    json_path::JsonPath,
+    remediation,
+    rule_set::RuleMetadata,
+    settings::{Action, CustomResource, ImageRef, Settings},
+    validation_result::{
+        ContainerImage, ContainerType, ImageEvaluation, PodRejectionReasons,
+        PodSpecValidationResult, Violation,
+    },
+    LOG_DRAIN,
 };
 
 use k8s_openapi::api::core::v1 as apicore;
 use oci_spec::distribution::Reference;
+use serde::Deserialize;
+use serde_json::Value;
+use slog::warn;
 
+/// Validate the images of the given Pod specification. `spec_path` is the
+/// path of the Pod specification inside of the resource being validated,
+/// e.g. `spec.template.spec`. `today` is used to evaluate the expiration of
+/// the entries of the allow and reject lists
 pub(crate) fn validate_pod_spec(
     pod_spec: &apicore::PodSpec,
+    spec_path: &str,
     settings: &Settings,
+    today: NaiveDate,
 ) -> PodSpecValidationResult {
-    let images = discover_images(pod_spec);
+    let images = discover_images(pod_spec, spec_path);
 
-    validate_images(&images, settings)
+    validate_images(&images, settings, today)
 }
 
-fn validate_images(images: &HashSet<&str>, settings: &Settings) -> PodSpecValidationResult {
-    let mut rejection_reasons = PodRejectionReasons::default();
+/// Validate only the ephemeral containers added to the Pod specification,
+/// e.g. by `kubectl debug`. The ephemeral containers cannot be changed nor
+/// removed, the new ones are the ones missing from the previous version of
+/// the Pod specification
+pub(crate) fn validate_new_ephemeral_containers(
+    pod_spec: &apicore::PodSpec,
+    previous_pod_spec: &apicore::PodSpec,
+    spec_path: &str,
+    settings: &Settings,
+    today: NaiveDate,
+) -> PodSpecValidationResult {
+    let previous_names: HashSet<&str> = previous_pod_spec
+        .ephemeral_containers
+        .iter()
+        .flatten()
+        .map(|container| container.name.as_str())
+        .collect();
+    let images: Vec<ContainerImage> = discover_images(pod_spec, spec_path)
+        .into_iter()
+        .filter(|container| {
+            container.container_type == ContainerType::EphemeralContainer
+                && !previous_names.contains(container.name.as_str())
+        })
+        .collect();
+
+    validate_images(&images, settings, today)
+}
 
-    for image in images {
-        let image_ref = Reference::from_str(image);
-        if let Ok(image_ref) = image_ref {
-            if !is_allowed_registry(image_ref.registry(), settings) {
-                rejection_reasons
-                    .registries_not_allowed
-                    .insert(image_ref.registry().to_owned());
-            }
+/// Downgrade to warnings the denials of the images that were already used, by
+/// a container with the same name, inside of the previous version of the
+/// resource
+pub(crate) fn grandfather_existing_images(
+    result: &mut PodSpecValidationResult,
+    previous_images: &[ContainerImage],
+) {
+    result.rejection_reasons.grandfather(|container| {
+        previous_images
+            .iter()
+            .any(|previous| previous.name == container.name && previous.image == container.image)
+    });
+}
+
+pub(crate) fn validate_images(
+    images: &[ContainerImage],
+    settings: &Settings,
+    today: NaiveDate,
+) -> PodSpecValidationResult {
+    let mut result = PodSpecValidationResult::default();
 
-            let tag = image_ref.tag().unwrap_or("latest");
-            if !is_allowed_tag(tag, settings) {
-                rejection_reasons.tags_not_allowed.insert(tag.to_owned());
+    for container in images {
+        let evaluation = match settings.for_container(container.container_type, &container.name) {
+            Some(settings) => {
+                evaluate_image(container, &settings, today, &mut result.rejection_reasons)
             }
+            None => ImageEvaluation {
+                container: container.clone(),
+                canonical_image: Reference::from_str(&container.image)
+                    .ok()
+                    .map(|image_ref| image_ref.whole()),
+                matched_rules: vec!["containerOverrides.exempt".to_string()],
+            },
+        };
+        result.evaluated_images.push(evaluation);
+    }
 
-            if !is_allowed_image(&image_ref.into(), settings) {
-                rejection_reasons
-                    .images_not_allowed
-                    .insert(image.to_string());
+    result
+}
+
+/// Evaluate the image of a container against all the rules, adding the
+/// violations found to `rejection_reasons`
+fn evaluate_image(
+    container: &ContainerImage,
+    settings: &Settings,
+    today: NaiveDate,
+    rejection_reasons: &mut PodRejectionReasons,
+) -> ImageEvaluation {
+    let image_ref = match Reference::from_str(&container.image) {
+        Ok(image_ref) => image_ref,
+        Err(_) => {
+            return ImageEvaluation {
+                container: container.clone(),
+                canonical_image: None,
+                matched_rules: Vec::new(),
             }
         }
+    };
+    let mut matched_rules = Vec::new();
+
+    // Keep in mind the settings are validated to prevent both allow and
+    // reject lists of a filter to be populated at the same time
+    let registries_rule = if settings.registries.allow.is_empty() {
+        "registries.reject"
+    } else {
+        "registries.allow"
+    };
+    if !is_allowed_registry(image_ref.registry(), settings, today) {
+        rejection_reasons.add(
+            container,
+            Violation::RegistryNotAllowed(image_ref.registry().to_owned()),
+            registries_rule,
+            settings.registries.action,
+        );
+        matched_rules.push(registries_rule.to_owned());
+    } else if let Some(starts) = settings
+        .registries
+        .reject
+        .scheduled(image_ref.registry(), today)
+    {
+        rejection_reasons.add(
+            container,
+            Violation::Upcoming {
+                violation: Box::new(Violation::RegistryNotAllowed(
+                    image_ref.registry().to_owned(),
+                )),
+                starts,
+            },
+            registries_rule,
+            Action::Warn,
+        );
+        matched_rules.push(registries_rule.to_owned());
+    } else if !settings.registries.allow.is_empty() {
+        if let Some(expires) = settings.registries.allow.expiring(
+            image_ref.registry(),
+            today,
+            settings.expiration.warning_days,
+        ) {
+            rejection_reasons.add(
+                container,
+                Violation::ExceptionExpiring {
+                    entry: image_ref.registry().to_owned(),
+                    expires,
+                },
+                registries_rule,
+                Action::Warn,
+            );
+        }
+        matched_rules.push(registries_rule.to_owned());
+    }
+
+    if let Some(constraints) = settings.repositories.get(image_ref.registry()) {
+        let repositories_rule = format!("repositories.{}", image_ref.registry());
+        if !is_allowed_repository(image_ref.registry(), image_ref.repository(), settings) {
+            rejection_reasons.add(
+                container,
+                Violation::RepositoryNotAllowed(format!(
+                    "{}/{}",
+                    image_ref.registry(),
+                    image_ref.repository()
+                )),
+                &repositories_rule,
+                constraints.action,
+            );
+        }
+        matched_rules.push(repositories_rule);
     }
 
-    if rejection_reasons.is_empty() {
-        PodSpecValidationResult::Allowed
+    if let Some(deprecated_registry) = find_deprecated_registry(
+        image_ref.registry(),
+        image_ref.repository(),
+        &settings.deprecated_registries,
+    ) {
+        rejection_reasons.add(
+            container,
+            Violation::DeprecatedRegistry {
+                registry: deprecated_registry.registry,
+                replacement: deprecated_registry.replacement,
+            },
+            "deprecatedRegistries",
+            deprecated_registry.action,
+        );
+        matched_rules.push("deprecatedRegistries".to_owned());
+    }
+
+    let tag = image_ref.tag().unwrap_or("latest");
+    if !is_allowed_tag(tag, settings, today) {
+        rejection_reasons.add(
+            container,
+            Violation::TagNotAllowed(tag.to_owned()),
+            "tags.reject",
+            settings.tags.action,
+        );
+        matched_rules.push("tags.reject".to_owned());
+    } else if let Some(starts) = settings.tags.reject.scheduled(tag, today) {
+        rejection_reasons.add(
+            container,
+            Violation::Upcoming {
+                violation: Box::new(Violation::TagNotAllowed(tag.to_owned())),
+                starts,
+            },
+            "tags.reject",
+            Action::Warn,
+        );
+        matched_rules.push("tags.reject".to_owned());
+    }
+
+    let canonical_image = image_ref.whole();
+    let images_rule = if settings.images.allow.is_empty() {
+        "images.reject"
     } else {
-        PodSpecValidationResult::NotAllowed(rejection_reasons)
+        "images.allow"
+    };
+    let image = ImageRef::new(image_ref.clone());
+    if !is_allowed_image(&image, settings, today) {
+        rejection_reasons.add(
+            container,
+            Violation::ImageNotAllowed,
+            images_rule,
+            settings.images.action,
+        );
+        matched_rules.push(images_rule.to_owned());
+    } else if let Some(starts) = matching_image_entry(&image, |entry| {
+        settings.images.reject.scheduled(entry, today).is_some()
+    })
+    .and_then(|entry| settings.images.reject.scheduled(&entry, today))
+    {
+        rejection_reasons.add(
+            container,
+            Violation::Upcoming {
+                violation: Box::new(Violation::ImageNotAllowed),
+                starts,
+            },
+            images_rule,
+            Action::Warn,
+        );
+        matched_rules.push(images_rule.to_owned());
+    } else if !settings.images.allow.is_empty() {
+        let expiring =
+            matching_image_entry(&image, |entry| settings.images.allow.applies(entry, today))
+                .and_then(|entry| {
+                    settings
+                        .images
+                        .allow
+                        .expiring(&entry, today, settings.expiration.warning_days)
+                        .map(|expires| (entry, expires))
+                });
+        if let Some((entry, expires)) = expiring {
+            // entries matching any tag are reported without the implicit one
+            let entry = if entry == image {
+                entry.whole()
+            } else {
+                format!("{}/{}", entry.registry(), entry.repository())
+            };
+            rejection_reasons.add(
+                container,
+                Violation::ExceptionExpiring { entry, expires },
+                images_rule,
+                Action::Warn,
+            );
+        }
+        matched_rules.push(images_rule.to_owned());
     }
-}
 
-fn discover_images(pod_spec: &apicore::PodSpec) -> HashSet<&str> {
-    let init_containers_images: Vec<&str> = pod_spec
-        .init_containers
-        .as_ref()
-        .and_then(|containers| {
-            containers
-                .iter()
-                .map(|container| container.image.as_deref())
-                .collect()
-        })
-        .unwrap_or_default();
+    if settings.images.require_digest {
+        if image_ref.digest().is_none() {
+            rejection_reasons.add(
+                container,
+                Violation::DigestRequired,
+                "images.requireDigest",
+                settings.images.action,
+            );
+        }
+        matched_rules.push("images.requireDigest".to_owned());
+    }
+
+    if let Some(findings) = rejection_reasons.containers.get_mut(container) {
+        for finding in findings {
+            finding.metadata =
+                rule_metadata(&image, &finding.violation, &finding.rule, settings, today);
+            finding.suggestion =
+                remediation::suggest(&image_ref, &finding.violation, settings, today);
+        }
+    }
+
+    ImageEvaluation {
+        container: container.clone(),
+        canonical_image: Some(canonical_image),
+        matched_rules,
+    }
+}
 
-    let ephemeral_containers_images: Vec<&str> = pod_spec
+pub(crate) fn discover_images(pod_spec: &apicore::PodSpec, spec_path: &str) -> Vec<ContainerImage> {
+    let init_containers = pod_spec.init_containers.iter().flatten().map(|container| {
+        // native sidecars are init containers that keep running along with
+        // the other containers
+        let container_type = if container.restart_policy.as_deref() == Some("Always") {
+            ContainerType::SidecarContainer
+        } else {
+            ContainerType::InitContainer
+        };
+        (
+            container_type,
+            container.name.as_str(),
+            container.image.as_deref(),
+            container.image_pull_policy.as_deref(),
+        )
+    });
+    let containers = pod_spec.containers.iter().map(|container| {
+        (
+            ContainerType::Container,
+            container.name.as_str(),
+            container.image.as_deref(),
+            container.image_pull_policy.as_deref(),
+        )
+    });
+    let ephemeral_containers = pod_spec
         .ephemeral_containers
-        .as_ref()
-        .and_then(|containers| {
-            containers
-                .iter()
-                .map(|container| container.image.as_deref())
-                .collect()
-        })
-        .unwrap_or_default();
+        .iter()
+        .flatten()
+        .map(|container| {
+            (
+                ContainerType::EphemeralContainer,
+                container.name.as_str(),
+                container.image.as_deref(),
+                container.image_pull_policy.as_deref(),
+            )
+        });
+    // OCI images and artifacts mounted as volumes, the other volumes are
+    // kept to preserve the position of the image ones
+    let image_volumes = pod_spec.volumes.iter().flatten().map(|volume| {
+        let source = volume.image.as_ref();
+        (
+            ContainerType::ImageVolume,
+            volume.name.as_str(),
+            source.and_then(|source| source.reference.as_deref()),
+            source.and_then(|source| source.pull_policy.as_deref()),
+        )
+    });
 
-    let container_images: Vec<&str> = pod_spec
-        .containers
+    container_images(spec_path, init_containers)
+        .chain(container_images(spec_path, containers))
+        .chain(container_images(spec_path, ephemeral_containers))
+        .chain(container_images(spec_path, image_volumes))
+        .collect()
+}
+
+/// Find the images of a custom resource, through the paths of its image
+/// fields and of its embedded Pod specifications. Values that are not strings
+/// are skipped, the images of the Pod specifications that cannot be parsed are
+/// still found through the `image` fields of their containers
+pub(crate) fn discover_custom_resource_images(
+    object: &Value,
+    custom_resource: &CustomResource,
+) -> Vec<ContainerImage> {
+    let images = custom_resource
+        .images
         .iter()
-        .filter_map(|container| container.image.as_deref())
-        .collect();
+        .flat_map(|path| resource_images(object, path));
+    let pod_spec_images = custom_resource
+        .pod_specs
+        .iter()
+        .flat_map(|path| path.select(object))
+        .flat_map(
+            |(spec_path, value)| match apicore::PodSpec::deserialize(value) {
+                Ok(pod_spec) => discover_images(&pod_spec, &spec_path),
+                Err(error) => {
+                    warn!(
+                        LOG_DRAIN,
+                        "cannot parse the Pod specification at {spec_path}, only the images of its containers are evaluated: {error}"
+                    );
+                    pod_spec_container_images(object, &spec_path)
+                }
+            },
+        );
 
-    init_containers_images
-        .into_iter()
-        .chain(ephemeral_containers_images)
-        .chain(container_images)
+    // the index identifies the image inside of the audit annotations, it
+    // must be unique among the images of the same type
+    let mut counters: HashMap<ContainerType, usize> = HashMap::new();
+    images
+        .chain(pod_spec_images)
+        .map(|mut container| {
+            let counter = counters.entry(container.container_type).or_default();
+            container.index = *counter;
+            *counter += 1;
+            container
+        })
         .collect()
 }
 
-fn is_allowed_registry(registry: &str, settings: &Settings) -> bool {
+/// The images of the containers of a Pod specification that cannot be parsed,
+/// found through their `image` fields
+fn pod_spec_container_images(object: &Value, spec_path: &str) -> Vec<ContainerImage> {
+    [
+        ("initContainers", ContainerType::InitContainer),
+        ("containers", ContainerType::Container),
+        ("ephemeralContainers", ContainerType::EphemeralContainer),
+    ]
+    .into_iter()
+    .filter_map(|(field, container_type)| {
+        let path = JsonPath::from_str(&format!("{spec_path}.{field}[*].image")).ok()?;
+        let images = resource_images(object, &path)
+            .into_iter()
+            .map(move |image| ContainerImage {
+                container_type,
+                ..image
+            });
+        Some(images)
+    })
+    .flatten()
+    .collect()
+}
+
+/// The images found at the given path. The name of an image is the `name`
+/// field of the object holding it, like the steps of a pipeline, falling back
+/// to the path of the object
+fn resource_images(object: &Value, path: &JsonPath) -> Vec<ContainerImage> {
+    let resource_image = |name: &str, field_path: String, image: &str| ContainerImage {
+        container_type: ContainerType::ResourceImage,
+        index: 0,
+        name: name.to_owned(),
+        field_path,
+        image: image.to_owned(),
+        pull_policy: None,
+    };
+
+    match path.split_last_field() {
+        Some((parent_path, field)) => parent_path
+            .select(object)
+            .into_iter()
+            .filter_map(|(parent_path, parent)| {
+                let image = parent.get(field)?.as_str()?;
+                let field_path = if parent_path.is_empty() {
+                    field.to_owned()
+                } else {
+                    format!("{parent_path}.{field}")
+                };
+                let name = match parent.get("name").and_then(Value::as_str) {
+                    Some(name) => name,
+                    None if parent_path.is_empty() => field,
+                    None => &parent_path,
+                };
+                Some(resource_image(name, field_path, image))
+            })
+            .collect(),
+        None => path
+            .select(object)
+            .into_iter()
+            .filter_map(|(field_path, value)| {
+                let image = value.as_str()?;
+                Some(resource_image(&field_path, field_path.clone(), image))
+            })
+            .collect(),
+    }
+}
+
+/// Build the `ContainerImage` of each `(type, name, image, pull policy)`
+/// tuple of a list of containers or volumes, skipping the ones without an
+/// image
+fn container_images<'a>(
+    spec_path: &'a str,
+    containers: impl Iterator<Item = (ContainerType, &'a str, Option<&'a str>, Option<&'a str>)> + 'a,
+) -> impl Iterator<Item = ContainerImage> + 'a {
+    containers
+        .enumerate()
+        .filter_map(move |(index, (container_type, name, image, pull_policy))| {
+            image.map(|image| ContainerImage {
+                container_type,
+                index,
+                name: name.to_owned(),
+                field_path: format!(
+                    "{spec_path}.{}[{index}].{}",
+                    container_type.field_name(),
+                    container_type.image_field()
+                ),
+                image: image.to_owned(),
+                pull_policy: pull_policy.map(str::to_owned),
+            })
+        })
+}
+
+pub(crate) fn is_allowed_registry(registry: &str, settings: &Settings, today: NaiveDate) -> bool {
     // Keep in mind the settings are validate to prevent both allow and reject
     // lists to be populated at the same time
 
@@ -96,26 +518,51 @@ fn is_allowed_registry(registry: &str, settings: &Settings) -> bool {
     }
 
     // if the registry is explicitly rejected, it is not allowed
-    if !settings.registries.reject.is_empty() && settings.registries.reject.contains(registry) {
+    if !settings.registries.reject.is_empty() && settings.registries.reject.applies(registry, today)
+    {
         return false;
     }
 
     if !settings.registries.allow.is_empty() {
-        return settings.registries.allow.contains(registry);
+        return settings.registries.allow.applies(registry, today);
     }
 
     true
 }
 
-fn is_allowed_tag(tag: &str, settings: &Settings) -> bool {
+fn is_allowed_repository(registry: &str, repository: &str, settings: &Settings) -> bool {
+    // if no constraints have been given for the registry, we allow all its repositories
+    let constraints = match settings.repositories.get(registry) {
+        Some(constraints) => constraints,
+        None => return true,
+    };
+
+    if let Some(max_depth) = constraints.max_depth {
+        if repository.split('/').count() > max_depth {
+            return false;
+        }
+    }
+
+    // The namespace must be made of the leading path segments of the repository:
+    // namespace "my-org" matches "my-org/app" and "my-org/team/app", but neither
+    // "my-org" nor "my-organization/app"
+    constraints.namespaces.is_empty()
+        || constraints.namespaces.iter().any(|namespace| {
+            repository
+                .strip_prefix(namespace.as_str())
+                .is_some_and(|rest| rest.len() > 1 && rest.starts_with('/'))
+        })
+}
+
+pub(crate) fn is_allowed_tag(tag: &str, settings: &Settings, today: NaiveDate) -> bool {
     if settings.tags.reject.is_empty() {
         return true;
     }
 
-    !settings.tags.reject.contains(tag)
+    !settings.tags.reject.applies(tag, today)
 }
 
-fn is_allowed_image(image_ref: &ImageRef, settings: &Settings) -> bool {
+fn is_allowed_image(image_ref: &ImageRef, settings: &Settings, today: NaiveDate) -> bool {
     // Keep in mind the settings are validate to prevent both allow and reject
     // lists to be populated at the same time
 
@@ -134,48 +581,146 @@ fn is_allowed_image(image_ref: &ImageRef, settings: &Settings) -> bool {
         return true;
     }
 
-    // helper closure for matching against repository or registry+repository
-    let matches_loose = |set: &std::collections::HashSet<ImageRef>| {
-        let contained_in_set_with_same_repo = Reference::from_str(image_ref.repository())
-            .ok()
-            .map(|r| set.contains(&ImageRef::new(r)))
-            .unwrap_or(false);
-
-        let contained_in_set_with_registry_plus_repo = {
-            let registry_repo = format!("{}/{}", image_ref.registry(), image_ref.repository());
-            Reference::from_str(&registry_repo)
-                .ok()
-                .map(|r| set.contains(&ImageRef::new(r)))
-                .unwrap_or(false)
-        };
-
-        contained_in_set_with_same_repo || contained_in_set_with_registry_plus_repo
-    };
-
-    if !settings.images.reject.is_empty() {
-        let reject = &settings.images.reject;
-        if reject.contains(image_ref) || matches_loose(reject) {
-            return false;
-        }
+    if !settings.images.reject.is_empty()
+        && matching_image_entry(image_ref, |entry| {
+            settings.images.reject.applies(entry, today)
+        })
+        .is_some()
+    {
+        return false;
     }
 
     if !settings.images.allow.is_empty() {
-        let allow = &settings.images.allow;
-        if allow.contains(image_ref) || matches_loose(allow) {
-            return true;
-        }
-        return false;
+        return matching_image_entry(image_ref, |entry| {
+            settings.images.allow.applies(entry, today)
+        })
+        .is_some();
     }
 
     true
 }
 
+/// Find the entry of a list matching the image: the full image ref, the
+/// image repository or the image registry+repository. `is_entry` tells
+/// whether the list has a relevant entry for the given ref, e.g. one that
+/// did not expire
+fn matching_image_entry(
+    image_ref: &ImageRef,
+    is_entry: impl Fn(&ImageRef) -> bool,
+) -> Option<ImageRef> {
+    if is_entry(image_ref) {
+        return Some(image_ref.clone());
+    }
+
+    let same_repo = Reference::from_str(image_ref.repository()).ok();
+    let registry_plus_repo = Reference::from_str(&format!(
+        "{}/{}",
+        image_ref.registry(),
+        image_ref.repository()
+    ))
+    .ok();
+    [same_repo, registry_plus_repo]
+        .into_iter()
+        .flatten()
+        .map(ImageRef::new)
+        .find(|entry| is_entry(entry))
+}
+
+/// The metadata of the list entry that produced the finding, if any
+fn rule_metadata(
+    image_ref: &ImageRef,
+    violation: &Violation,
+    rule: &str,
+    settings: &Settings,
+    today: NaiveDate,
+) -> Option<RuleMetadata> {
+    let metadata = match (violation, rule) {
+        (Violation::Upcoming { violation, .. }, _) => {
+            return rule_metadata(image_ref, violation, rule, settings, today);
+        }
+        (Violation::RegistryNotAllowed(registry), "registries.reject") => {
+            settings.registries.reject.metadata(registry.as_str())
+        }
+        (Violation::TagNotAllowed(tag), "tags.reject") => {
+            settings.tags.reject.metadata(tag.as_str())
+        }
+        (Violation::ImageNotAllowed, "images.reject") => matching_image_entry(image_ref, |entry| {
+            settings.images.reject.applies(entry, today)
+                || settings.images.reject.scheduled(entry, today).is_some()
+        })
+        .and_then(|entry| settings.images.reject.metadata(&entry)),
+        (Violation::ExceptionExpiring { .. }, "registries.allow") => {
+            settings.registries.allow.metadata(image_ref.registry())
+        }
+        (Violation::ExceptionExpiring { .. }, "images.allow") => {
+            matching_image_entry(image_ref, |entry| {
+                settings.images.allow.applies(entry, today)
+            })
+            .and_then(|entry| settings.images.allow.metadata(&entry))
+        }
+        _ => None,
+    };
+    metadata.cloned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rstest::*;
 
-    use crate::settings::{Images, Registries, Tags};
+    use std::collections::HashMap;
+
+    use crate::settings::{Action, Images, Registries, RepositoryConstraints, Tags};
+
+    /// The day the images are validated
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 6, 1).unwrap()
+    }
+
+    /// Wrap each image inside of a container of the Pod specification
+    fn containers(images: &[&str]) -> Vec<ContainerImage> {
+        images
+            .iter()
+            .enumerate()
+            .map(|(index, image)| ContainerImage {
+                container_type: ContainerType::Container,
+                index,
+                name: format!("container-{index}"),
+                field_path: format!("spec.containers[{index}].image"),
+                image: image.to_string(),
+                pull_policy: None,
+            })
+            .collect()
+    }
+
+    /// Build the result expected when the images, wrapped by `containers`,
+    /// produce the given `(image, violation, rule, action)` findings
+    fn not_allowed<R: AsRef<str>>(
+        images: &[&str],
+        findings: Vec<(&str, Violation, R, Action)>,
+    ) -> PodRejectionReasons {
+        let containers = containers(images);
+        let mut rejection_reasons = PodRejectionReasons::default();
+        for (image, violation, rule, action) in findings {
+            let container = containers
+                .iter()
+                .find(|container| container.image == image)
+                .expect("image not found");
+            rejection_reasons.add(container, violation, rule.as_ref(), action);
+        }
+        rejection_reasons
+    }
+
+    /// Validate the images, wrapped by `containers`, ignoring the remediation
+    /// suggestions: they are covered by the tests of the remediation module
+    fn violations(images: &[&str], settings: &Settings) -> PodRejectionReasons {
+        let mut rejection_reasons =
+            validate_images(&containers(images), settings, today()).rejection_reasons;
+        for finding in rejection_reasons.containers.values_mut().flatten() {
+            finding.suggestion = None;
+        }
+        rejection_reasons
+    }
 
     #[rstest]
     #[case::empty_pod_spec(
@@ -191,10 +736,12 @@ mod tests {
         apicore::PodSpec {
             containers: vec![
                 apicore::Container {
+                    name: "busybox".to_string(),
                     image: Some("busybox:1.0.0".to_string()),
                     ..apicore::Container::default()
                 },
                 apicore::Container {
+                    name: "alpine".to_string(),
                     image: Some("alpine:3.12".to_string()),
                     ..apicore::Container::default()
                 },
@@ -203,22 +750,28 @@ mod tests {
             ephemeral_containers: None,
             ..apicore::PodSpec::default()
         },
-        vec!["busybox:1.0.0", "alpine:3.12"],
+        vec![
+            (ContainerType::Container, "busybox", "spec.template.spec.containers[0].image", "busybox:1.0.0"),
+            (ContainerType::Container, "alpine", "spec.template.spec.containers[1].image", "alpine:3.12"),
+        ],
     )]
     #[case::init_containers(
         apicore::PodSpec {
             containers: vec![
                 apicore::Container {
+                    name: "busybox".to_string(),
                     image: Some("busybox:1.0.0".to_string()),
                     ..apicore::Container::default()
                 },
             ],
             init_containers: Some(vec![
                 apicore::Container {
+                    name: "init-busybox".to_string(),
                     image: Some("busybox:1.0.0".to_string()),
                     ..apicore::Container::default()
                 },
                 apicore::Container {
+                    name: "init-alpine".to_string(),
                     image: Some("alpine:3.12".to_string()),
                     ..apicore::Container::default()
                 },
@@ -226,12 +779,17 @@ mod tests {
             ephemeral_containers: None,
             ..apicore::PodSpec::default()
         },
-        vec!["busybox:1.0.0", "alpine:3.12"],
+        vec![
+            (ContainerType::InitContainer, "init-busybox", "spec.template.spec.initContainers[0].image", "busybox:1.0.0"),
+            (ContainerType::InitContainer, "init-alpine", "spec.template.spec.initContainers[1].image", "alpine:3.12"),
+            (ContainerType::Container, "busybox", "spec.template.spec.containers[0].image", "busybox:1.0.0"),
+        ],
     )]
     #[case::ephemeral_containers(
         apicore::PodSpec {
             containers: vec![
                 apicore::Container {
+                    name: "busybox".to_string(),
                     image: Some("busybox:1.0.0".to_string()),
                     ..apicore::Container::default()
                 },
@@ -239,75 +797,546 @@ mod tests {
             init_containers: None,
             ephemeral_containers: Some(vec![
                 apicore::EphemeralContainer {
+                    name: "debug-busybox".to_string(),
                     image: Some("busybox:1.0.0".to_string()),
                     ..apicore::EphemeralContainer::default()
                 },
                 apicore::EphemeralContainer {
+                    name: "debug-alpine".to_string(),
                     image: Some("alpine:3.12".to_string()),
                     ..apicore::EphemeralContainer::default()
                 },
             ]),
             ..apicore::PodSpec::default()
         },
-        vec!["busybox:1.0.0", "alpine:3.12"],
+        vec![
+            (ContainerType::Container, "busybox", "spec.template.spec.containers[0].image", "busybox:1.0.0"),
+            (ContainerType::EphemeralContainer, "debug-busybox", "spec.template.spec.ephemeralContainers[0].image", "busybox:1.0.0"),
+            (ContainerType::EphemeralContainer, "debug-alpine", "spec.template.spec.ephemeralContainers[1].image", "alpine:3.12"),
+        ],
+    )]
+    #[case::native_sidecars(
+        apicore::PodSpec {
+            containers: vec![
+                apicore::Container {
+                    name: "busybox".to_string(),
+                    image: Some("busybox:1.0.0".to_string()),
+                    ..apicore::Container::default()
+                },
+            ],
+            init_containers: Some(vec![
+                apicore::Container {
+                    name: "init-busybox".to_string(),
+                    image: Some("busybox:1.0.0".to_string()),
+                    ..apicore::Container::default()
+                },
+                apicore::Container {
+                    name: "proxy".to_string(),
+                    image: Some("envoyproxy/envoy:v1.31.0".to_string()),
+                    restart_policy: Some("Always".to_string()),
+                    ..apicore::Container::default()
+                },
+            ]),
+            ephemeral_containers: None,
+            ..apicore::PodSpec::default()
+        },
+        vec![
+            (ContainerType::InitContainer, "init-busybox", "spec.template.spec.initContainers[0].image", "busybox:1.0.0"),
+            (ContainerType::SidecarContainer, "proxy", "spec.template.spec.initContainers[1].image", "envoyproxy/envoy:v1.31.0"),
+            (ContainerType::Container, "busybox", "spec.template.spec.containers[0].image", "busybox:1.0.0"),
+        ],
+    )]
+    #[case::image_volumes(
+        apicore::PodSpec {
+            containers: vec![
+                apicore::Container {
+                    name: "busybox".to_string(),
+                    image: Some("busybox:1.0.0".to_string()),
+                    ..apicore::Container::default()
+                },
+            ],
+            volumes: Some(vec![
+                apicore::Volume {
+                    name: "cache".to_string(),
+                    empty_dir: Some(apicore::EmptyDirVolumeSource::default()),
+                    ..apicore::Volume::default()
+                },
+                apicore::Volume {
+                    name: "models".to_string(),
+                    image: Some(apicore::ImageVolumeSource {
+                        reference: Some("quay.io/my-org/models:v1".to_string()),
+                        pull_policy: Some("IfNotPresent".to_string()),
+                    }),
+                    ..apicore::Volume::default()
+                },
+            ]),
+            ..apicore::PodSpec::default()
+        },
+        vec![
+            (ContainerType::Container, "busybox", "spec.template.spec.containers[0].image", "busybox:1.0.0"),
+            (ContainerType::ImageVolume, "models", "spec.template.spec.volumes[1].image.reference", "quay.io/my-org/models:v1"),
+        ],
+    )]
+    #[case::container_without_image(
+        apicore::PodSpec {
+            containers: vec![
+                apicore::Container {
+                    name: "busybox".to_string(),
+                    image: Some("busybox:1.0.0".to_string()),
+                    ..apicore::Container::default()
+                },
+            ],
+            init_containers: Some(vec![
+                apicore::Container {
+                    name: "no-image".to_string(),
+                    image: None,
+                    ..apicore::Container::default()
+                },
+                apicore::Container {
+                    name: "init-alpine".to_string(),
+                    image: Some("alpine:3.12".to_string()),
+                    ..apicore::Container::default()
+                },
+            ]),
+            ephemeral_containers: None,
+            ..apicore::PodSpec::default()
+        },
+        vec![
+            (ContainerType::InitContainer, "init-alpine", "spec.template.spec.initContainers[1].image", "alpine:3.12"),
+            (ContainerType::Container, "busybox", "spec.template.spec.containers[0].image", "busybox:1.0.0"),
+        ],
     )]
     fn discover_images_from_pod_spec(
         #[case] pod_spec: apicore::PodSpec,
-        #[case] expected_images: Vec<&str>,
+        #[case] expected_images: Vec<(ContainerType, &str, &str, &str)>,
     ) {
-        let images: HashSet<&str> = discover_images(&pod_spec);
-        let expected_images: HashSet<&str> = expected_images.into_iter().collect();
+        let images: Vec<(ContainerType, String, String, String)> =
+            discover_images(&pod_spec, "spec.template.spec")
+                .into_iter()
+                .map(|container| {
+                    (
+                        container.container_type,
+                        container.name,
+                        container.field_path,
+                        container.image,
+                    )
+                })
+                .collect();
+        let expected_images: Vec<(ContainerType, String, String, String)> = expected_images
+            .into_iter()
+            .map(|(container_type, name, field_path, image)| {
+                (
+                    container_type,
+                    name.to_string(),
+                    field_path.to_string(),
+                    image.to_string(),
+                )
+            })
+            .collect();
         assert_eq!(
             images, expected_images,
             "got {images:?} instead of {expected_images:?}"
         );
     }
 
+    #[test]
+    fn rules_per_container_type() {
+        let pod_spec = apicore::PodSpec {
+            containers: vec![apicore::Container {
+                name: "app".to_string(),
+                image: Some("docker.io/nicolaka/netshoot:latest".to_string()),
+                ..apicore::Container::default()
+            }],
+            init_containers: Some(vec![apicore::Container {
+                name: "proxy".to_string(),
+                image: Some("docker.io/envoyproxy/envoy:v1.31.0".to_string()),
+                restart_policy: Some("Always".to_string()),
+                ..apicore::Container::default()
+            }]),
+            ephemeral_containers: Some(vec![apicore::EphemeralContainer {
+                name: "debugger".to_string(),
+                image: Some("docker.io/nicolaka/netshoot:latest".to_string()),
+                ..apicore::EphemeralContainer::default()
+            }]),
+            ..apicore::PodSpec::default()
+        };
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com"]},
+                "containerTypes": {
+                    "sidecarContainers": {"registries": {"allow": ["registry.corp.com", "docker.io"]}},
+                    "ephemeralContainers": {
+                        "registries": {"allow": ["docker.io"]},
+                        "images": {"allow": ["docker.io/nicolaka/netshoot"]}
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let result = validate_pod_spec(&pod_spec, "spec", &settings, today());
+
+        let rejected: Vec<String> = result
+            .rejection_reasons
+            .containers
+            .iter()
+            .map(|(container, findings)| {
+                let violations: Vec<String> = findings
+                    .iter()
+                    .map(|finding| finding.violation.to_string())
+                    .collect();
+                format!(
+                    "{} {}: {}",
+                    container.container_type,
+                    container.name,
+                    violations.join(", ")
+                )
+            })
+            .collect();
+        assert_eq!(
+            rejected,
+            vec!["container app: registry docker.io not allowed"]
+        );
+    }
+
+    #[test]
+    fn rules_per_container_name() {
+        let pod_spec = apicore::PodSpec {
+            containers: vec![
+                apicore::Container {
+                    name: "app".to_string(),
+                    image: Some("docker.io/library/nginx:1.27".to_string()),
+                    ..apicore::Container::default()
+                },
+                apicore::Container {
+                    name: "istio-proxy".to_string(),
+                    image: Some("docker.io/istio/proxyv2:1.24.0".to_string()),
+                    ..apicore::Container::default()
+                },
+                apicore::Container {
+                    name: "linkerd-proxy".to_string(),
+                    image: Some("cr.l5d.io/linkerd/proxy:edge-24.11.1".to_string()),
+                    ..apicore::Container::default()
+                },
+            ],
+            ..apicore::PodSpec::default()
+        };
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com"]},
+                "containerOverrides": [
+                    {"names": ["istio-*"], "exempt": true},
+                    {"names": ["linkerd-proxy"], "registries": {"allow": ["cr.l5d.io"]}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let result = validate_pod_spec(&pod_spec, "spec", &settings, today());
+
+        let rejected: Vec<&str> = result
+            .rejection_reasons
+            .containers
+            .keys()
+            .map(|container| container.name.as_str())
+            .collect();
+        assert_eq!(rejected, vec!["app"]);
+        let matched_rules: Vec<(&str, Vec<String>)> = result
+            .evaluated_images
+            .iter()
+            .map(|evaluation| {
+                (
+                    evaluation.container.name.as_str(),
+                    evaluation.matched_rules.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            matched_rules,
+            vec![
+                ("app", vec!["registries.allow".to_string()]),
+                ("istio-proxy", vec!["containerOverrides.exempt".to_string()]),
+                ("linkerd-proxy", vec!["registries.allow".to_string()]),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::mesh_sidecar("istio-proxy", "registry.my-mesh.io/istio/proxyv2:1.24.0", true)]
+    #[case::renamed_sidecar("proxy", "registry.my-mesh.io/istio/proxyv2:1.24.0", false)]
+    #[case::spoofed_name("istio-proxy", "docker.io/library/nginx:1.27", false)]
+    fn overrides_selected_by_name_only(
+        #[case] name: &str,
+        #[case] image: &str,
+        #[case] is_accepted: bool,
+    ) {
+        let pod_spec = apicore::PodSpec {
+            containers: vec![apicore::Container {
+                name: name.to_string(),
+                image: Some(image.to_string()),
+                ..apicore::Container::default()
+            }],
+            ..apicore::PodSpec::default()
+        };
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"allow": ["registry.corp.com"]},
+                "containerOverrides": [
+                    {"names": ["istio-proxy"], "registries": {"allow": ["registry.my-mesh.io"]}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let result = validate_pod_spec(&pod_spec, "spec", &settings, today());
+
+        assert_eq!(
+            result.rejection_reasons.containers.is_empty(),
+            is_accepted,
+            "unexpected rejections {:?}",
+            result.rejection_reasons
+        );
+    }
+
+    #[test]
+    fn discover_images_from_custom_resource() {
+        let custom_resource: CustomResource = serde_json::from_value(serde_json::json!({
+            "group": "example.com",
+            "kind": "Pipeline",
+            "images": ["spec.steps[*].image", "spec.builder.image"],
+            "podSpecs": ["spec.runners[*].podSpec"],
+        }))
+        .unwrap();
+        let object = serde_json::json!({
+            "spec": {
+                "builder": {"image": "golang:1.23"},
+                "steps": [
+                    {"name": "build", "image": "golang:1.23"},
+                    {"name": "script", "image": 42},
+                    {"name": "push", "image": "gcr.io/kaniko-project/executor:v1.23.2"},
+                ],
+                "runners": [
+                    {"podSpec": {"containers": [{"name": "runner", "image": "busybox:1.36"}]}},
+                    {"podSpec": {"containers": [{"name": "runner", "image": "alpine:3.20"}]}},
+                ],
+            }
+        });
+
+        let images: Vec<(ContainerType, usize, String, String, String)> =
+            discover_custom_resource_images(&object, &custom_resource)
+                .into_iter()
+                .map(|container| {
+                    (
+                        container.container_type,
+                        container.index,
+                        container.name,
+                        container.field_path,
+                        container.image,
+                    )
+                })
+                .collect();
+        let expected: Vec<(ContainerType, usize, String, String, String)> = [
+            (
+                ContainerType::ResourceImage,
+                0,
+                "build",
+                "spec.steps[0].image",
+                "golang:1.23",
+            ),
+            (
+                ContainerType::ResourceImage,
+                1,
+                "push",
+                "spec.steps[2].image",
+                "gcr.io/kaniko-project/executor:v1.23.2",
+            ),
+            (
+                ContainerType::ResourceImage,
+                2,
+                "spec.builder",
+                "spec.builder.image",
+                "golang:1.23",
+            ),
+            (
+                ContainerType::Container,
+                0,
+                "runner",
+                "spec.runners[0].podSpec.containers[0].image",
+                "busybox:1.36",
+            ),
+            (
+                ContainerType::Container,
+                1,
+                "runner",
+                "spec.runners[1].podSpec.containers[0].image",
+                "alpine:3.20",
+            ),
+        ]
+        .into_iter()
+        .map(|(container_type, index, name, field_path, image)| {
+            (
+                container_type,
+                index,
+                name.to_string(),
+                field_path.to_string(),
+                image.to_string(),
+            )
+        })
+        .collect();
+        assert_eq!(images, expected);
+    }
+
+    #[test]
+    fn discover_images_from_invalid_pod_spec() {
+        let custom_resource: CustomResource = serde_json::from_value(serde_json::json!({
+            "group": "example.com",
+            "kind": "Runner",
+            "podSpecs": ["spec.template.spec"],
+        }))
+        .unwrap();
+        // the port must be a number, the Pod specification cannot be parsed
+        let object = serde_json::json!({
+            "spec": {
+                "template": {
+                    "spec": {
+                        "initContainers": [{"name": "setup", "image": "busybox:1.36"}],
+                        "containers": [
+                            {"name": "runner", "image": "alpine:3.20", "ports": [{"containerPort": "http"}]},
+                        ],
+                    }
+                }
+            }
+        });
+
+        let images: Vec<(ContainerType, String, String)> =
+            discover_custom_resource_images(&object, &custom_resource)
+                .into_iter()
+                .map(|container| (container.container_type, container.name, container.image))
+                .collect();
+        assert_eq!(
+            images,
+            vec![
+                (
+                    ContainerType::InitContainer,
+                    "setup".to_string(),
+                    "busybox:1.36".to_string()
+                ),
+                (
+                    ContainerType::Container,
+                    "runner".to_string(),
+                    "alpine:3.20".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn grandfather_unchanged_images() {
+        let container = |name: &str, image: &str| apicore::Container {
+            name: name.to_string(),
+            image: Some(image.to_string()),
+            ..apicore::Container::default()
+        };
+        let previous_pod_spec = apicore::PodSpec {
+            containers: vec![
+                container("app", "docker.io/library/nginx:1.27"),
+                container("sidecar", "docker.io/library/busybox:1.36"),
+            ],
+            ..apicore::PodSpec::default()
+        };
+        let pod_spec = apicore::PodSpec {
+            containers: vec![
+                container("app", "docker.io/library/nginx:1.27"),
+                container("sidecar", "docker.io/library/busybox:1.37"),
+                container("exporter", "docker.io/library/nginx:1.27"),
+            ],
+            ..apicore::PodSpec::default()
+        };
+        let settings = Settings {
+            registries: Registries {
+                reject: vec!["docker.io".to_string()].into_iter().collect(),
+                ..Registries::default()
+            },
+            ..Settings::default()
+        };
+
+        let mut result = validate_pod_spec(&pod_spec, "spec", &settings, today());
+        grandfather_existing_images(&mut result, &discover_images(&previous_pod_spec, "spec"));
+
+        let findings: Vec<(&str, Action)> = result
+            .rejection_reasons
+            .containers
+            .iter()
+            .flat_map(|(container, findings)| {
+                findings
+                    .iter()
+                    .map(|finding| (container.name.as_str(), finding.action))
+            })
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("app", Action::Warn),
+                ("sidecar", Action::Deny),
+                ("exporter", Action::Deny),
+            ]
+        );
+    }
+
     #[rstest]
     #[case::block_implicit_latest(
         vec!["busybox"],
         vec!["latest"],
-        Err(vec!["latest"]),
+        Err(vec![("busybox", "latest")]),
     )]
     #[case::tag_part_of_reject_list(
         vec!["busybox:latest"],
         vec!["latest"],
-        Err(vec!["latest"]),
+        Err(vec![("busybox:latest", "latest")]),
     )]
     #[case::tag_not_part_of_reject_list(
         vec!["busybox:1.0.0"],
         vec!["latest"],
         Ok(()),
     )]
+    #[case::pinned_only_to_digest(
+        vec!["busybox@sha256:3fc9b689459d738f8c88a3a48aa9e33542016b7a4052e001aaa536fca74813cb"],
+        vec!["latest"],
+        Err(vec![("busybox@sha256:3fc9b689459d738f8c88a3a48aa9e33542016b7a4052e001aaa536fca74813cb", "latest")]),
+    )]
     fn validation_with_rejected_tags_constraint(
         #[case] images: Vec<&str>,
         #[case] settings_tags_rejected: Vec<&str>,
-        #[case] expected_result: Result<(), Vec<&str>>,
+        #[case] expected_result: Result<(), Vec<(&str, &str)>>,
     ) {
-        let images: HashSet<&str> = images.into_iter().collect();
         let settings = Settings {
             tags: Tags {
                 reject: settings_tags_rejected
                     .into_iter()
                     .map(|t| t.to_string())
                     .collect(),
+                ..Tags::default()
             },
             ..Settings::default()
         };
         let expected_result = if let Err(tags_not_allowed) = expected_result {
-            let tags_not_allowed = tags_not_allowed
+            let findings = tags_not_allowed
                 .into_iter()
-                .map(|image| image.to_string())
+                .map(|(image, tag)| {
+                    (
+                        image,
+                        Violation::TagNotAllowed(tag.to_string()),
+                        "tags.reject",
+                        Action::Deny,
+                    )
+                })
                 .collect();
-            PodSpecValidationResult::NotAllowed(PodRejectionReasons {
-                tags_not_allowed,
-                ..PodRejectionReasons::default()
-            })
+            not_allowed(&images, findings)
         } else {
-            PodSpecValidationResult::Allowed
+            PodRejectionReasons::default()
         };
 
-        let result = validate_images(&images, &settings);
+        let result = violations(&images, &settings);
         assert_eq!(
             result, expected_result,
             "got: {result:?} instead of {expected_result:?}"
@@ -318,7 +1347,10 @@ mod tests {
     #[case::image_from_registry_part_of_the_reject_list(
         vec!["busybox:1.0.0", "ghcr.io/kubewarden/policy-server:1.0.0"],
         vec!["docker.io", "ghcr.io"],
-        Err(vec!["docker.io", "ghcr.io"]),
+        Err(vec![
+            ("busybox:1.0.0", "docker.io"),
+            ("ghcr.io/kubewarden/policy-server:1.0.0", "ghcr.io"),
+        ]),
     )]
     #[case::image_from_registry_not_part_of_the_reject_list(
         vec!["ghcr.io/kubewarden/policy-server:1.0.0"],
@@ -328,9 +1360,8 @@ mod tests {
     fn validation_with_registry_reject_constraint(
         #[case] images: Vec<&str>,
         #[case] settings_registries_to_reject: Vec<&str>,
-        #[case] expected_result: Result<(), Vec<&str>>,
+        #[case] expected_result: Result<(), Vec<(&str, &str)>>,
     ) {
-        let images: HashSet<&str> = images.into_iter().collect();
         let settings = Settings {
             registries: Registries {
                 reject: settings_registries_to_reject
@@ -342,20 +1373,23 @@ mod tests {
             ..Settings::default()
         };
         let expected_result = if let Err(registries_not_allowed) = expected_result {
-            let registries_not_allowed = registries_not_allowed
+            let findings = registries_not_allowed
                 .into_iter()
-                .map(|image| image.to_string())
+                .map(|(image, registry)| {
+                    (
+                        image,
+                        Violation::RegistryNotAllowed(registry.to_string()),
+                        "registries.reject",
+                        Action::Deny,
+                    )
+                })
                 .collect();
-            PodSpecValidationResult::NotAllowed(PodRejectionReasons {
-                registries_not_allowed,
-                ..PodRejectionReasons::default()
-            })
+            not_allowed(&images, findings)
         } else {
-            PodSpecValidationResult::Allowed
+            PodRejectionReasons::default()
         };
 
-        let images: HashSet<&str> = images.into_iter().collect();
-        let result = validate_images(&images, &settings);
+        let result = violations(&images, &settings);
         assert_eq!(
             result, expected_result,
             "got: {result:?} instead of {expected_result:?}"
@@ -366,7 +1400,10 @@ mod tests {
     #[case::image_from_registry_not_part_of_the_allow_list(
         vec!["busybox:1.0.0", "docker.io/alpine:1.0.0", "ghcr.io/kubewarden/policy-server:1.0.0"],
         vec!["ghcr.io"],
-        Err(vec!["docker.io"]),
+        Err(vec![
+            ("busybox:1.0.0", "docker.io"),
+            ("docker.io/alpine:1.0.0", "docker.io"),
+        ]),
     )]
     #[case::image_from_registry_part_of_the_allow_list(
         vec!["busybox:1.0.0", "docker.io/alpine:1.0.0", "ghcr.io/kubewarden/policy-server:1.0.0"],
@@ -376,9 +1413,8 @@ mod tests {
     fn validation_with_registry_allow_constraint(
         #[case] images: Vec<&str>,
         #[case] settings_registries_to_allow: Vec<&str>,
-        #[case] expected_result: Result<(), Vec<&str>>,
+        #[case] expected_result: Result<(), Vec<(&str, &str)>>,
     ) {
-        let images: HashSet<&str> = images.into_iter().collect();
         let settings = Settings {
             registries: Registries {
                 allow: settings_registries_to_allow
@@ -390,20 +1426,23 @@ mod tests {
             ..Settings::default()
         };
         let expected_result = if let Err(registries_not_allowed) = expected_result {
-            let registries_not_allowed = registries_not_allowed
+            let findings = registries_not_allowed
                 .into_iter()
-                .map(|image| image.to_string())
+                .map(|(image, registry)| {
+                    (
+                        image,
+                        Violation::RegistryNotAllowed(registry.to_string()),
+                        "registries.allow",
+                        Action::Deny,
+                    )
+                })
                 .collect();
-            PodSpecValidationResult::NotAllowed(PodRejectionReasons {
-                registries_not_allowed,
-                ..PodRejectionReasons::default()
-            })
+            not_allowed(&images, findings)
         } else {
-            PodSpecValidationResult::Allowed
+            PodRejectionReasons::default()
         };
 
-        let images: HashSet<&str> = images.into_iter().collect();
-        let result = validate_images(&images, &settings);
+        let result = violations(&images, &settings);
         assert_eq!(
             result, expected_result,
             "got: {result:?} instead of {expected_result:?}"
@@ -475,7 +1514,6 @@ mod tests {
         #[case] settings_images_to_allow: Vec<&str>,
         #[case] expected_result: Result<(), Vec<&str>>,
     ) {
-        let images: HashSet<&str> = images.into_iter().collect();
         let settings = Settings {
             images: Images {
                 allow: settings_images_to_allow
@@ -487,19 +1525,23 @@ mod tests {
             ..Settings::default()
         };
         let expected_result = if let Err(images_not_allowed) = expected_result {
-            let images_not_allowed = images_not_allowed
+            let findings = images_not_allowed
                 .into_iter()
-                .map(|image| image.to_string())
+                .map(|image| {
+                    (
+                        image,
+                        Violation::ImageNotAllowed,
+                        "images.allow",
+                        Action::Deny,
+                    )
+                })
                 .collect();
-            PodSpecValidationResult::NotAllowed(PodRejectionReasons {
-                images_not_allowed,
-                ..PodRejectionReasons::default()
-            })
+            not_allowed(&images, findings)
         } else {
-            PodSpecValidationResult::Allowed
+            PodRejectionReasons::default()
         };
 
-        let result = validate_images(&images, &settings);
+        let result = violations(&images, &settings);
         assert_eq!(
             result, expected_result,
             r#"got: {result:?} instead of {expected_result:?}"#
@@ -583,7 +1625,6 @@ mod tests {
         #[case] settings_images_to_reject: Vec<&str>,
         #[case] expected_result: Result<(), Vec<&str>>,
     ) {
-        let images: HashSet<&str> = images.into_iter().collect();
         let settings = Settings {
             images: Images {
                 reject: settings_images_to_reject
@@ -595,30 +1636,108 @@ mod tests {
             ..Settings::default()
         };
         let expected_result = if let Err(images_not_allowed) = expected_result {
-            let images_not_allowed = images_not_allowed
+            let findings = images_not_allowed
                 .into_iter()
-                .map(|image| image.to_string())
+                .map(|image| {
+                    (
+                        image,
+                        Violation::ImageNotAllowed,
+                        "images.reject",
+                        Action::Deny,
+                    )
+                })
                 .collect();
-            PodSpecValidationResult::NotAllowed(PodRejectionReasons {
-                images_not_allowed,
-                ..PodRejectionReasons::default()
-            })
+            not_allowed(&images, findings)
         } else {
-            PodSpecValidationResult::Allowed
+            PodRejectionReasons::default()
         };
 
-        let result = validate_images(&images, &settings);
+        let result = violations(&images, &settings);
         assert_eq!(
             result, expected_result,
             r#"got: {result:?} instead of {expected_result:?}"#
         );
     }
 
+    #[rstest]
+    #[case::official_image(
+        vec!["busybox:1.0.0", "docker.io/library/alpine:1.0.0", "ghcr.io/other-org/app:1.0.0"],
+        Ok(()),
+    )]
+    #[case::image_outside_of_the_allowed_namespace(
+        vec!["bitnami/redis:6.0", "ghcr.io/our-org/app:1.0.0", "ghcr.io/our-organization/app:1.0.0"],
+        Err(vec![
+            ("bitnami/redis:6.0", "docker.io/bitnami/redis"),
+            ("ghcr.io/our-organization/app:1.0.0", "ghcr.io/our-organization/app"),
+        ]),
+    )]
+    #[case::namespace_is_not_a_repository(
+        vec!["ghcr.io/our-org:1.0.0"],
+        Err(vec![("ghcr.io/our-org:1.0.0", "ghcr.io/our-org")]),
+    )]
+    #[case::repository_too_deep(
+        vec!["ghcr.io/our-org/team/app:1.0.0", "ghcr.io/our-org/team/app/nested:1.0.0"],
+        Err(vec![(
+            "ghcr.io/our-org/team/app/nested:1.0.0",
+            "ghcr.io/our-org/team/app/nested",
+        )]),
+    )]
+    fn validation_with_repository_constraints(
+        #[case] images: Vec<&str>,
+        #[case] expected_result: Result<(), Vec<(&str, &str)>>,
+    ) {
+        let settings = Settings {
+            repositories: HashMap::from([
+                (
+                    "docker.io".to_string(),
+                    RepositoryConstraints {
+                        namespaces: vec!["library".to_string()].into_iter().collect(),
+                        ..RepositoryConstraints::default()
+                    },
+                ),
+                (
+                    "ghcr.io".to_string(),
+                    RepositoryConstraints {
+                        namespaces: vec!["our-org".to_string(), "other-org".to_string()]
+                            .into_iter()
+                            .collect(),
+                        max_depth: Some(3),
+                        ..RepositoryConstraints::default()
+                    },
+                ),
+            ])
+            .into(),
+            ..Settings::default()
+        };
+        let expected_result = if let Err(repositories_not_allowed) = expected_result {
+            let findings = repositories_not_allowed
+                .into_iter()
+                .map(|(image, repository)| {
+                    (
+                        image,
+                        Violation::RepositoryNotAllowed(repository.to_string()),
+                        format!("repositories.{}", repository.split('/').next().unwrap()),
+                        Action::Deny,
+                    )
+                })
+                .collect();
+            not_allowed(&images, findings)
+        } else {
+            PodRejectionReasons::default()
+        };
+
+        let result = violations(&images, &settings);
+        assert_eq!(
+            result, expected_result,
+            "got: {result:?} instead of {expected_result:?}"
+        );
+    }
+
     #[rstest]
     #[case::empty_settings(
         vec!["busybox"],
         Settings::default(),
-        PodSpecValidationResult::Allowed)]
+        PodRejectionReasons::default())]
     #[case::registry_allowed_but_tag_rejected(
         vec!["busybox"],
         Settings{
@@ -628,13 +1747,14 @@ mod tests {
             },
             tags: Tags {
                 reject: vec!["latest".to_string()].into_iter().collect(),
+                ..Tags::default()
             },
             ..Settings::default()
         },
-        PodSpecValidationResult::NotAllowed(PodRejectionReasons {
-            tags_not_allowed: vec!["latest".to_string()].into_iter().collect(),
-            ..PodRejectionReasons::default()
-        }),
+        not_allowed(
+            &["busybox"],
+            vec![("busybox", Violation::TagNotAllowed("latest".to_string()), "tags.reject", Action::Deny)],
+        ),
     )]
     #[case::registry_allowed_but_image_rejected(
         vec!["busybox:1.0.0"],
@@ -649,10 +1769,10 @@ mod tests {
             },
             ..Settings::default()
         },
-        PodSpecValidationResult::NotAllowed(PodRejectionReasons {
-            images_not_allowed: vec!["busybox:1.0.0".to_string()].into_iter().collect(),
-            ..PodRejectionReasons::default()
-        }),
+        not_allowed(
+            &["busybox:1.0.0"],
+            vec![("busybox:1.0.0", Violation::ImageNotAllowed, "images.reject", Action::Deny)],
+        ),
     )]
     #[case::registry_allowed_and_image_not_rejected(
         vec!["busybox:2.0.0"],
@@ -667,18 +1787,379 @@ mod tests {
             },
             ..Settings::default()
         },
-        PodSpecValidationResult::Allowed,
+        PodRejectionReasons::default(),
+    )]
+    #[case::actions_of_the_filters(
+        vec!["busybox:latest", "quay.io/coreos/etcd:v3.4.12"],
+        Settings{
+            registries: Registries {
+                reject: vec!["quay.io".to_string()].into_iter().collect(),
+                action: Action::Warn,
+                ..Registries::default()
+            },
+            tags: Tags {
+                reject: vec!["latest".to_string()].into_iter().collect(),
+                action: Action::Audit,
+            },
+            ..Settings::default()
+        },
+        not_allowed(
+            &["busybox:latest", "quay.io/coreos/etcd:v3.4.12"],
+            vec![
+                ("busybox:latest", Violation::TagNotAllowed("latest".to_string()), "tags.reject", Action::Audit),
+                ("quay.io/coreos/etcd:v3.4.12", Violation::RegistryNotAllowed("quay.io".to_string()), "registries.reject", Action::Warn),
+            ],
+        ),
+    )]
+    #[case::digest_required(
+        vec!["busybox:1.0.0", "busybox@sha256:3fc9b689459d738f8c88a3a48aa9e33542016b7a4052e001aaa536fca74813cb"],
+        Settings{
+            images: Images {
+                require_digest: true,
+                ..Images::default()
+            },
+            ..Settings::default()
+        },
+        not_allowed(
+            &["busybox:1.0.0", "busybox@sha256:3fc9b689459d738f8c88a3a48aa9e33542016b7a4052e001aaa536fca74813cb"],
+            vec![("busybox:1.0.0", Violation::DigestRequired, "images.requireDigest", Action::Deny)],
+        ),
+    )]
+    #[case::deprecated_registry(
+        vec!["k8s.gcr.io/pause:3.1", "registry.k8s.io/pause:3.1"],
+        Settings::default(),
+        not_allowed(
+            &["k8s.gcr.io/pause:3.1", "registry.k8s.io/pause:3.1"],
+            vec![(
+                "k8s.gcr.io/pause:3.1",
+                Violation::DeprecatedRegistry {
+                    registry: "k8s.gcr.io".to_string(),
+                    replacement: "registry.k8s.io".to_string(),
+                },
+                "deprecatedRegistries",
+                Action::Warn,
+            )],
+        ),
     )]
     fn validation_with_special_settings(
         #[case] images: Vec<&str>,
         #[case] settings: Settings,
-        #[case] expected_result: PodSpecValidationResult,
+        #[case] expected_result: PodRejectionReasons,
     ) {
-        let images: HashSet<&str> = images.into_iter().collect();
-        let result = validate_images(&images, &settings);
+        let result = violations(&images, &settings);
         assert_eq!(
             result, expected_result,
             "got: {result:?} instead of {expected_result:?}"
         );
     }
+
+    #[rstest]
+    #[case::no_rules(
+        "busybox",
+        Settings::default(),
+        Some("docker.io/library/busybox:latest"),
+        Vec::new()
+    )]
+    #[case::allow_lists(
+        "ghcr.io/my-org/app:1.0.0",
+        Settings {
+            registries: Registries {
+                allow: vec!["ghcr.io".to_string()].into_iter().collect(),
+                ..Registries::default()
+            },
+            images: Images {
+                allow: vec![Reference::from_str("ghcr.io/my-org/app").unwrap().into()].into_iter().collect(),
+                ..Images::default()
+            },
+            ..Settings::default()
+        },
+        Some("ghcr.io/my-org/app:1.0.0"),
+        vec!["registries.allow", "images.allow"],
+    )]
+    #[case::reject_lists(
+        "quay.io/coreos/etcd:latest",
+        Settings {
+            registries: Registries {
+                reject: vec!["quay.io".to_string()].into_iter().collect(),
+                ..Registries::default()
+            },
+            tags: Tags {
+                reject: vec!["latest".to_string()].into_iter().collect(),
+                ..Tags::default()
+            },
+            images: Images {
+                reject: vec![Reference::from_str("quay.io/coreos/etcd").unwrap().into()].into_iter().collect(),
+                ..Images::default()
+            },
+            ..Settings::default()
+        },
+        Some("quay.io/coreos/etcd:latest"),
+        vec!["registries.reject", "tags.reject", "images.reject"],
+    )]
+    #[case::repositories_and_deprecated_registries(
+        "k8s.gcr.io/pause:3.1",
+        Settings {
+            repositories: HashMap::from([(
+                "k8s.gcr.io".to_string(),
+                RepositoryConstraints::default(),
+            )])
+            .into(),
+            ..Settings::default()
+        },
+        Some("k8s.gcr.io/pause:3.1"),
+        vec!["repositories.k8s.gcr.io", "deprecatedRegistries"],
+    )]
+    #[case::invalid_image("INVALID", Settings::default(), None, Vec::new())]
+    fn evaluated_images_report_matched_rules(
+        #[case] image: &str,
+        #[case] settings: Settings,
+        #[case] expected_canonical_image: Option<&str>,
+        #[case] expected_matched_rules: Vec<&str>,
+    ) {
+        let result = validate_images(&containers(&[image]), &settings, today());
+
+        assert_eq!(result.evaluated_images.len(), 1);
+        let evaluation = &result.evaluated_images[0];
+        assert_eq!(evaluation.container.image, image);
+        assert_eq!(
+            evaluation.canonical_image.as_deref(),
+            expected_canonical_image
+        );
+        assert_eq!(evaluation.matched_rules, expected_matched_rules);
+    }
+
+    #[test]
+    fn findings_carry_remediation_suggestions() {
+        let settings = Settings {
+            registries: Registries {
+                allow: vec!["registry.corp.com".to_string()].into_iter().collect(),
+                ..Registries::default()
+            },
+            tags: Tags {
+                reject: vec!["latest".to_string()].into_iter().collect(),
+                ..Tags::default()
+            },
+            images: Images {
+                allow: vec![Reference::from_str("registry.corp.com/mirror/nginx:1.25.3")
+                    .unwrap()
+                    .into()]
+                .into_iter()
+                .collect(),
+                ..Images::default()
+            },
+            ..Settings::default()
+        };
+
+        let result = validate_images(&containers(&["nginx"]), &settings, today());
+
+        let suggestions: Vec<(&str, Option<&str>)> = result
+            .rejection_reasons
+            .containers
+            .values()
+            .flatten()
+            .map(|finding| (finding.rule.as_str(), finding.suggestion.as_deref()))
+            .collect();
+        assert_eq!(
+            suggestions,
+            vec![
+                (
+                    "registries.allow",
+                    Some("use registry.corp.com/mirror/nginx instead of docker.io/library/nginx")
+                ),
+                ("tags.reject", None),
+                (
+                    "images.allow",
+                    Some("use registry.corp.com/mirror/nginx instead of docker.io/library/nginx")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn findings_carry_rule_metadata() {
+        let settings: Settings = serde_json::from_str(
+            r#"{
+                "registries": {"reject": [{"registry": "quay.io", "description": "unmaintained mirror", "owner": "platform-team"}]},
+                "tags": {"reject": ["latest"]},
+                "images": {"reject": [{"image": "busybox", "ticket": "SEC-42"}]}
+            }"#,
+        )
+        .unwrap();
+
+        let result = validate_images(
+            &containers(&["quay.io/coreos/etcd:latest", "busybox:1.36"]),
+            &settings,
+            today(),
+        );
+
+        let metadata: Vec<(&str, Option<String>)> = result
+            .rejection_reasons
+            .containers
+            .values()
+            .flatten()
+            .map(|finding| {
+                (
+                    finding.rule.as_str(),
+                    finding
+                        .metadata
+                        .as_ref()
+                        .map(|metadata| metadata.to_string()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            metadata,
+            vec![
+                (
+                    "registries.reject",
+                    Some("unmaintained mirror, owner: platform-team".to_string())
+                ),
+                ("tags.reject", None),
+                ("images.reject", Some("ticket: SEC-42".to_string())),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::active_exception(
+        r#"{"registries": {"allow": ["ghcr.io", {"registry": "docker.io", "expires": "2026-12-31"}]}}"#,
+        Vec::new()
+    )]
+    #[case::expiring_registry_exception(
+        r#"{"registries": {"allow": ["ghcr.io", {"registry": "docker.io", "owner": "team-a", "expires": "2026-06-20"}]}}"#,
+        vec![(
+            "registries.allow",
+            "exception docker.io expires on 2026-06-20 [owner: team-a, expires: 2026-06-20]",
+            Action::Warn,
+        )]
+    )]
+    #[case::expired_registry_exception(
+        r#"{"registries": {"allow": ["ghcr.io", {"registry": "docker.io", "expires": "2026-05-31"}]}}"#,
+        vec![(
+            "registries.allow",
+            "registry docker.io not allowed",
+            Action::Deny,
+        )]
+    )]
+    #[case::expiring_image_exception(
+        r#"{"images": {"allow": [{"image": "docker.io/legacy/app", "expires": "2026-06-01"}]}}"#,
+        vec![(
+            "images.allow",
+            "exception docker.io/legacy/app expires on 2026-06-01 [expires: 2026-06-01]",
+            Action::Warn,
+        )]
+    )]
+    #[case::expired_image_exception(
+        r#"{"images": {"allow": [{"image": "docker.io/legacy/app", "expires": "2026-05-31"}]}}"#,
+        vec![("images.allow", "image not allowed", Action::Deny)]
+    )]
+    #[case::custom_warning_window(
+        r#"{
+            "registries": {"allow": [{"registry": "docker.io", "expires": "2026-06-20"}]},
+            "expiration": {"warningDays": 7}
+        }"#,
+        Vec::new()
+    )]
+    #[case::expired_rejection(
+        r#"{"tags": {"reject": [{"tag": "1.0.0", "expires": "2026-05-31"}]}}"#,
+        Vec::new()
+    )]
+    fn time_bound_exceptions(
+        #[case] settings: &str,
+        #[case] expected_findings: Vec<(&str, &str, Action)>,
+    ) {
+        let settings: Settings = serde_json::from_str(settings).unwrap();
+
+        let result = validate_images(
+            &containers(&["docker.io/legacy/app:1.0.0"]),
+            &settings,
+            today(),
+        );
+
+        let findings: Vec<(&str, String, Action)> = result
+            .rejection_reasons
+            .containers
+            .values()
+            .flatten()
+            .map(|finding| {
+                let mut description = finding.violation.to_string();
+                if let Some(metadata) = &finding.metadata {
+                    description.push_str(&format!(" [{metadata}]"));
+                }
+                (finding.rule.as_str(), description, finding.action)
+            })
+            .collect();
+        let expected_findings: Vec<(&str, String, Action)> = expected_findings
+            .into_iter()
+            .map(|(rule, description, action)| (rule, description.to_string(), action))
+            .collect();
+        assert_eq!(findings, expected_findings);
+    }
+
+    #[rstest]
+    #[case::registry_before_cut_off(
+        r#"{"registries": {"reject": [{"registry": "docker.io", "starts": "2027-01-01"}]}}"#,
+        vec![(
+            "registries.reject",
+            "registry docker.io not allowed starting 2027-01-01 [starts: 2027-01-01]",
+            Action::Warn,
+        )]
+    )]
+    #[case::registry_after_cut_off(
+        r#"{"registries": {"reject": [{"registry": "docker.io", "starts": "2026-06-01"}]}}"#,
+        vec![(
+            "registries.reject",
+            "registry docker.io not allowed [starts: 2026-06-01]",
+            Action::Deny,
+        )]
+    )]
+    #[case::tag_before_cut_off(
+        r#"{"tags": {"reject": [{"tag": "1.0.0", "starts": "2026-07-01", "ticket": "OPS-1234"}]}}"#,
+        vec![(
+            "tags.reject",
+            "tag 1.0.0 not allowed starting 2026-07-01 [ticket: OPS-1234, starts: 2026-07-01]",
+            Action::Warn,
+        )]
+    )]
+    #[case::image_before_cut_off(
+        r#"{"images": {"reject": [{"image": "docker.io/legacy/app", "starts": "2026-07-01"}]}}"#,
+        vec![(
+            "images.reject",
+            "image not allowed starting 2026-07-01 [starts: 2026-07-01]",
+            Action::Warn,
+        )]
+    )]
+    #[case::image_after_cut_off(
+        r#"{"images": {"reject": [{"image": "docker.io/legacy/app", "starts": "2026-05-01"}]}}"#,
+        vec![(
+            "images.reject",
+            "image not allowed [starts: 2026-05-01]",
+            Action::Deny,
+        )]
+    )]
+    fn sunset_schedule(
+        #[case] settings: &str,
+        #[case] expected_findings: Vec<(&str, &str, Action)>,
+    ) {
+        let settings: Settings = serde_json::from_str(settings).unwrap();
+
+        let result = validate_images(
+            &containers(&["docker.io/legacy/app:1.0.0"]),
+            &settings,
+            today(),
+        );
+
+        let findings: Vec<(&str, String, Action)> = result
+            .rejection_reasons
+            .containers
+            .values()
+            .flatten()
+            .map(|finding| (finding.rule.as_str(), finding.to_string(), finding.action))
+            .collect();
+        let expected_findings: Vec<(&str, String, Action)> = expected_findings
+            .into_iter()
+            .map(|(rule, description, action)| (rule, description.to_string(), action))
+            .collect();
+        assert_eq!(findings, expected_findings);
+    }
 }
@@ -1,83 +1,412 @@
-use std::collections::BTreeSet;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+};
 
+use chrono::NaiveDate;
 use kubewarden_policy_sdk::response::ValidationResponse;
+use oci_spec::distribution::Reference;
+use serde_json::json;
 
-#[derive(Default, Debug, PartialEq, Eq)]
+use crate::{
+    rule_set::RuleMetadata,
+    settings::{Action, RejectionMessage},
+    template,
+};
+
+/// The kinds of containers a Pod can have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum ContainerType {
+    InitContainer,
+    /// Init container with an `Always` restart policy, running along with
+    /// the other containers
+    SidecarContainer,
+    Container,
+    EphemeralContainer,
+    /// Volume mounting an OCI image or artifact, it is not a container
+    ImageVolume,
+    /// Image field of a custom resource, found through the paths of the
+    /// settings
+    ResourceImage,
+}
+
+impl ContainerType {
+    /// The name of the PodSpec field holding this kind of containers. The
+    /// images of the custom resources are identified by `images`
+    pub(crate) fn field_name(&self) -> &'static str {
+        match self {
+            ContainerType::InitContainer | ContainerType::SidecarContainer => "initContainers",
+            ContainerType::Container => "containers",
+            ContainerType::EphemeralContainer => "ephemeralContainers",
+            ContainerType::ImageVolume => "volumes",
+            ContainerType::ResourceImage => "images",
+        }
+    }
+
+    /// The path of the image inside of the entry of the PodSpec field
+    pub(crate) fn image_field(&self) -> &'static str {
+        match self {
+            ContainerType::ImageVolume => "image.reference",
+            _ => "image",
+        }
+    }
+}
+
+impl fmt::Display for ContainerType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerType::InitContainer => write!(f, "init container"),
+            ContainerType::SidecarContainer => write!(f, "sidecar container"),
+            ContainerType::Container => write!(f, "container"),
+            ContainerType::EphemeralContainer => write!(f, "ephemeral container"),
+            ContainerType::ImageVolume => write!(f, "image volume"),
+            ContainerType::ResourceImage => write!(f, "image"),
+        }
+    }
+}
+
+/// An image referenced by a container of the Pod specification.
+///
+/// Containers are sorted by type, then by their position inside of the
+/// Pod specification
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct ContainerImage {
+    pub(crate) container_type: ContainerType,
+    /// Position of the container inside of its list. The images of the
+    /// custom resources are numbered by type across all their paths
+    pub(crate) index: usize,
+    pub(crate) name: String,
+    /// Path of the image field, e.g. `spec.template.spec.initContainers[1].image`
+    pub(crate) field_path: String,
+    /// The image, as written inside of the Pod specification
+    pub(crate) image: String,
+    /// The pull policy of the image, when provided
+    pub(crate) pull_policy: Option<String>,
+}
+
+impl fmt::Display for ContainerImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({}: {})",
+            self.container_type, self.name, self.field_path, self.image
+        )
+    }
+}
+
+/// A rule violated by an image
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Violation {
+    RegistryNotAllowed(String),
+    RepositoryNotAllowed(String),
+    DeprecatedRegistry {
+        registry: String,
+        replacement: String,
+    },
+    TagNotAllowed(String),
+    ImageNotAllowed,
+    DigestRequired,
+    /// The image is allowed by an exception that is about to expire
+    ExceptionExpiring {
+        entry: String,
+        expires: NaiveDate,
+    },
+    /// The violation of a rule that is not enforced yet
+    Upcoming {
+        violation: Box<Violation>,
+        starts: NaiveDate,
+    },
+    /// The violation of an image that was already used by the container
+    /// before the update of the resource
+    Grandfathered(Box<Violation>),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::RegistryNotAllowed(registry) => {
+                write!(f, "registry {registry} not allowed")
+            }
+            Violation::RepositoryNotAllowed(repository) => {
+                write!(f, "repository {repository} not allowed")
+            }
+            Violation::DeprecatedRegistry {
+                registry,
+                replacement,
+            } => write!(
+                f,
+                "deprecated registry {registry}, use {replacement} instead"
+            ),
+            Violation::TagNotAllowed(tag) => write!(f, "tag {tag} not allowed"),
+            Violation::ImageNotAllowed => write!(f, "image not allowed"),
+            Violation::DigestRequired => write!(f, "image not pinned to a digest"),
+            Violation::ExceptionExpiring { entry, expires } => {
+                write!(f, "exception {entry} expires on {expires}")
+            }
+            Violation::Upcoming { violation, starts } => {
+                write!(f, "{violation} starting {starts}")
+            }
+            Violation::Grandfathered(violation) => {
+                write!(f, "{violation}, allowed until the image is changed")
+            }
+        }
+    }
+}
+
+/// A violation, together with the rule that produced it and the action
+/// configured for that rule
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Finding {
+    pub(crate) violation: Violation,
+    /// The rule that produced the violation, e.g. `registries.allow`
+    pub(crate) rule: String,
+    pub(crate) action: Action,
+    /// The metadata of the list entry that produced the violation, e.g.
+    /// the owner of a rejected registry
+    pub(crate) metadata: Option<RuleMetadata>,
+    /// How to fix the violation, e.g. `most recent allowed tag is 1.25.3`
+    pub(crate) suggestion: Option<String>,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.violation)?;
+        if let Some(metadata) = &self.metadata {
+            write!(f, " [{metadata}]")?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " ({suggestion})")?;
+        }
+        Ok(())
+    }
+}
+
+/// The violations found inside of a Pod specification, grouped by container
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub(crate) struct PodRejectionReasons {
-    pub(crate) registries_not_allowed: BTreeSet<String>,
-    pub(crate) tags_not_allowed: BTreeSet<String>,
-    pub(crate) images_not_allowed: BTreeSet<String>,
+    pub(crate) containers: BTreeMap<ContainerImage, Vec<Finding>>,
 }
 
 impl PodRejectionReasons {
-    pub fn is_empty(&self) -> bool {
-        self.registries_not_allowed.is_empty()
-            && self.tags_not_allowed.is_empty()
-            && self.images_not_allowed.is_empty()
+    pub fn add(
+        &mut self,
+        container: &ContainerImage,
+        violation: Violation,
+        rule: &str,
+        action: Action,
+    ) {
+        self.containers
+            .entry(container.clone())
+            .or_default()
+            .push(Finding {
+                violation,
+                rule: rule.to_string(),
+                action,
+                metadata: None,
+                suggestion: None,
+            });
     }
+
+    /// Downgrade the violations of the given containers associated with the
+    /// `deny` action to warnings, marking them as grandfathered
+    pub fn grandfather<F: Fn(&ContainerImage) -> bool>(&mut self, is_grandfathered: F) {
+        self.containers
+            .iter_mut()
+            .filter(|(container, _)| is_grandfathered(container))
+            .flat_map(|(_, findings)| findings)
+            .filter(|finding| finding.action == Action::Deny)
+            .for_each(|finding| {
+                let violation =
+                    std::mem::replace(&mut finding.violation, Violation::ImageNotAllowed);
+                finding.violation = Violation::Grandfathered(Box::new(violation));
+                finding.action = Action::Warn;
+            });
+    }
+
+    /// Downgrade the violations associated with the `deny` action to
+    /// warnings, returning their description
+    pub fn bypass_denials(&mut self) -> Vec<String> {
+        let bypassed = self.describe(Action::Deny);
+        self.containers
+            .values_mut()
+            .flatten()
+            .filter(|finding| finding.action == Action::Deny)
+            .for_each(|finding| finding.action = Action::Warn);
+        bypassed
+    }
+
+    /// Describe the violations associated with the given action, one entry
+    /// per container
+    fn describe(&self, action: Action) -> Vec<String> {
+        self.containers
+            .iter()
+            .filter_map(|(container, findings)| {
+                let violations: Vec<String> = findings
+                    .iter()
+                    .filter(|finding| finding.action == action)
+                    .map(|finding| finding.to_string())
+                    .collect();
+                (!violations.is_empty()).then(|| format!("{container}: {}", violations.join(", ")))
+            })
+            .collect()
+    }
+
+    /// Render the template once per violation associated with the given
+    /// action. Identical messages are reported only once
+    fn render(&self, action: Action, message_template: &str) -> Vec<String> {
+        let mut messages: Vec<String> = Vec::new();
+        for (container, findings) in &self.containers {
+            let image_ref = Reference::from_str(&container.image).ok();
+            for finding in findings.iter().filter(|finding| finding.action == action) {
+                let metadata = finding.metadata.clone().unwrap_or_default();
+                let message = template::render(message_template, |placeholder| {
+                    let value = match placeholder {
+                        "container" => container.name.clone(),
+                        "containerType" => container.container_type.to_string(),
+                        "fieldPath" => container.field_path.clone(),
+                        "image" => container.image.clone(),
+                        "registry" => image_ref.as_ref()?.registry().to_string(),
+                        "tag" => image_ref.as_ref()?.tag().unwrap_or("latest").to_string(),
+                        "rule" => finding.rule.clone(),
+                        "violation" => finding.violation.to_string(),
+                        "suggestion" => finding.suggestion.clone().unwrap_or_default(),
+                        "description" => metadata.description.clone().unwrap_or_default(),
+                        "owner" => metadata.owner.clone().unwrap_or_default(),
+                        "ticket" => metadata.ticket.clone().unwrap_or_default(),
+                        "expires" => metadata
+                            .expires
+                            .map(|expires| expires.to_string())
+                            .unwrap_or_default(),
+                        _ => return None,
+                    };
+                    Some(value)
+                });
+                if !messages.contains(&message) {
+                    messages.push(message);
+                }
+            }
+        }
+        messages
+    }
+}
+
+/// The outcome of the evaluation of an image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ImageEvaluation {
+    pub(crate) container: ContainerImage,
+    /// The image in its canonical form, e.g. `docker.io/library/nginx:latest`.
+    /// `None` when the image is not a valid reference
+    pub(crate) canonical_image: Option<String>,
+    /// The rules that allowed or rejected the image, e.g. `registries.allow`
+    pub(crate) matched_rules: Vec<String>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) enum PodSpecValidationResult {
-    Allowed,
-    NotAllowed(PodRejectionReasons),
+/// The outcome of the validation of a Pod specification
+#[derive(Default, Debug, PartialEq, Eq)]
+pub(crate) struct PodSpecValidationResult {
+    /// All the images that have been evaluated, in discovery order
+    pub(crate) evaluated_images: Vec<ImageEvaluation>,
+    /// The violations found. The request is still accepted when none of them
+    /// is associated with the `deny` action
+    pub(crate) rejection_reasons: PodRejectionReasons,
 }
 
-impl From<PodSpecValidationResult> for ValidationResponse {
-    fn from(validation_result: PodSpecValidationResult) -> ValidationResponse {
-        match validation_result {
-            PodSpecValidationResult::Allowed => ValidationResponse {
+impl PodSpecValidationResult {
+    /// The decision taken for the given container: the most severe action of
+    /// its findings
+    fn decision(&self, container: &ContainerImage) -> &'static str {
+        let actions: Vec<Action> = self
+            .rejection_reasons
+            .containers
+            .get(container)
+            .map(|findings| findings.iter().map(|finding| finding.action).collect())
+            .unwrap_or_default();
+
+        if actions.contains(&Action::Deny) {
+            "denied"
+        } else if actions.contains(&Action::Warn) {
+            "warned"
+        } else if actions.contains(&Action::Audit) {
+            "audited"
+        } else {
+            "allowed"
+        }
+    }
+
+    /// Build one audit annotation per evaluated image. The key identifies
+    /// the container, e.g. `image.initContainers.1`, the value is a JSON
+    /// object describing the decision
+    fn audit_annotations(&self) -> HashMap<String, String> {
+        self.evaluated_images
+            .iter()
+            .map(|evaluation| {
+                let container = &evaluation.container;
+                let key = format!(
+                    "image.{}.{}",
+                    container.container_type.field_name(),
+                    container.index
+                );
+                let mut value = json!({
+                    "image": evaluation.canonical_image.as_deref().unwrap_or(&container.image),
+                    "decision": self.decision(container),
+                    "rules": evaluation.matched_rules,
+                });
+                let name_key = match container.container_type {
+                    ContainerType::ImageVolume => "volume",
+                    ContainerType::ResourceImage => "name",
+                    _ => "container",
+                };
+                value[name_key] = json!(container.name);
+                if let Some(pull_policy) = &container.pull_policy {
+                    value["pullPolicy"] = json!(pull_policy);
+                }
+                (key, value.to_string())
+            })
+            .collect()
+    }
+
+    /// Build the response sent back to the API server. The message and the
+    /// status code of the rejections can be customized by the user
+    pub(crate) fn into_response(self, rejection_message: &RejectionMessage) -> ValidationResponse {
+        let rejection_reasons = &self.rejection_reasons;
+        let errors = match &rejection_message.template {
+            Some(message_template) => rejection_reasons.render(Action::Deny, message_template),
+            None => rejection_reasons.describe(Action::Deny),
+        };
+
+        let warnings = rejection_reasons.describe(Action::Warn);
+        let warnings = (!warnings.is_empty()).then_some(warnings);
+
+        let mut audit_annotations = self.audit_annotations();
+        let audited = rejection_reasons.describe(Action::Audit);
+        if !audited.is_empty() {
+            audit_annotations.insert("violations".to_string(), audited.join("; "));
+        }
+        let audit_annotations = (!audit_annotations.is_empty()).then_some(audit_annotations);
+
+        if errors.is_empty() {
+            return ValidationResponse {
                 accepted: true,
                 message: None,
                 code: None,
                 mutated_object: None,
-                audit_annotations: None,
-                warnings: None,
-            },
-            PodSpecValidationResult::NotAllowed(rejection_reasons) => {
-                let mut errors = Vec::new();
-                if !rejection_reasons.registries_not_allowed.is_empty() {
-                    errors.push(format!(
-                        "registries not allowed: {}",
-                        rejection_reasons
-                            .registries_not_allowed
-                            .into_iter()
-                            .collect::<Vec<String>>()
-                            .join(", ")
-                    ));
-                }
-                if !rejection_reasons.tags_not_allowed.is_empty() {
-                    errors.push(format!(
-                        "tags not allowed: {}",
-                        rejection_reasons
-                            .tags_not_allowed
-                            .into_iter()
-                            .collect::<Vec<String>>()
-                            .join(", ")
-                    ))
-                }
-                if !rejection_reasons.images_not_allowed.is_empty() {
-                    errors.push(format!(
-                        "images not allowed: {}",
-                        rejection_reasons
-                            .images_not_allowed
-                            .into_iter()
-                            .collect::<Vec<String>>()
-                            .join(", ")
-                    ))
-                }
-                ValidationResponse {
-                    accepted: false,
-                    message: Some(format!(
-                        "not allowed, reported errors: {}",
-                        errors.join("; ")
-                    )),
-                    code: None,
-                    mutated_object: None,
-                    warnings: None,
-                    audit_annotations: None,
-                }
-            }
+                audit_annotations,
+                warnings,
+            };
+        }
+        let message = if rejection_message.template.is_some() {
+            errors.join("; ")
+        } else {
+            format!("not allowed, reported errors: {}", errors.join("; "))
+        };
+        ValidationResponse {
+            accepted: false,
+            message: Some(message),
+            code: rejection_message.code,
+            mutated_object: None,
+            warnings,
+            audit_annotations,
         }
     }
 }
@@ -87,26 +416,59 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    fn container(
+        container_type: ContainerType,
+        index: usize,
+        name: &str,
+        image: &str,
+    ) -> ContainerImage {
+        ContainerImage {
+            container_type,
+            index,
+            name: name.to_string(),
+            field_path: format!("spec.{}[{index}].image", container_type.field_name()),
+            image: image.to_string(),
+            pull_policy: None,
+        }
+    }
+
     #[rstest]
-    #[case::allowed(PodSpecValidationResult::Allowed, vec![])]
+    #[case::allowed(PodRejectionReasons::default(), vec![])]
     #[case::not_allowed(
-        PodSpecValidationResult::NotAllowed(PodRejectionReasons {
-            registries_not_allowed: vec!["registry1".to_string()].into_iter().collect(),
-            tags_not_allowed: vec!["tag1".to_string()].into_iter().collect(),
-            images_not_allowed: vec!["image1".to_string()].into_iter().collect(),
-        }),
-        vec!["registry1", "tag1", "image1"]
+        PodRejectionReasons {
+            containers: vec![(
+                container(ContainerType::Container, 0, "nginx", "registry1/repository1/image1:tag1"),
+                vec![
+                    Finding { violation: Violation::RegistryNotAllowed("registry1".to_string()), rule: "registries.allow".to_string(), action: Action::Deny, metadata: None, suggestion: None },
+                    Finding { violation: Violation::RepositoryNotAllowed("registry1/repository1".to_string()), rule: "repositories.registry1".to_string(), action: Action::Deny, metadata: None, suggestion: None },
+                    Finding {
+                        violation: Violation::DeprecatedRegistry {
+                            registry: "registry2".to_string(),
+                            replacement: "registry3".to_string(),
+                        },
+                        rule: "deprecatedRegistries".to_string(),
+                        action: Action::Deny,
+                        metadata: None,
+                        suggestion: None,
+                    },
+                    Finding { violation: Violation::TagNotAllowed("tag1".to_string()), rule: "tags.reject".to_string(), action: Action::Deny, metadata: None, suggestion: None },
+                    Finding { violation: Violation::ImageNotAllowed, rule: "images.allow".to_string(), action: Action::Deny, metadata: None, suggestion: None },
+                ],
+            )].into_iter().collect(),
+        },
+        vec!["registry1", "tag1", "image", "registry1/repository1", "registry2", "registry3", "nginx"]
     )]
     fn pod_spec_validation_result_into_validation_response(
-        #[case] result: PodSpecValidationResult,
+        #[case] rejection_reasons: PodRejectionReasons,
         #[case] expected_error_msgs: Vec<&str>,
     ) {
-        let given_result_is_allowed = match &result {
-            PodSpecValidationResult::Allowed => true,
-            PodSpecValidationResult::NotAllowed(_) => false,
+        let given_result_is_allowed = rejection_reasons.containers.is_empty();
+        let result = PodSpecValidationResult {
+            rejection_reasons,
+            ..PodSpecValidationResult::default()
         };
 
-        let validation_response: ValidationResponse = result.into();
+        let validation_response = result.into_response(&RejectionMessage::default());
 
         if expected_error_msgs.is_empty() {
             assert!(validation_response.accepted);
@@ -137,4 +499,396 @@ mod tests {
         assert_eq!(validation_response.audit_annotations, None);
         assert_eq!(validation_response.warnings, None);
     }
+
+    #[test]
+    fn rejection_message_lists_containers_in_a_stable_order() {
+        let mut rejection_reasons = PodRejectionReasons::default();
+        let containers = vec![
+            container(
+                ContainerType::EphemeralContainer,
+                0,
+                "debugger",
+                "busybox:latest",
+            ),
+            container(ContainerType::Container, 10, "sidecar", "busybox:latest"),
+            container(
+                ContainerType::Container,
+                2,
+                "app",
+                "docker.io/my-org/app:1.0.0",
+            ),
+            container(ContainerType::InitContainer, 1, "setup", "busybox:latest"),
+        ];
+        for container in &containers {
+            if container.image.ends_with(":latest") {
+                rejection_reasons.add(
+                    container,
+                    Violation::TagNotAllowed("latest".to_string()),
+                    "tags.reject",
+                    Action::Deny,
+                );
+            }
+            rejection_reasons.add(
+                container,
+                Violation::RegistryNotAllowed("docker.io".to_string()),
+                "registries.allow",
+                Action::Deny,
+            );
+        }
+
+        let validation_response = PodSpecValidationResult {
+            rejection_reasons,
+            ..PodSpecValidationResult::default()
+        }
+        .into_response(&RejectionMessage::default());
+
+        assert!(!validation_response.accepted);
+        assert_eq!(
+            validation_response.message.as_deref(),
+            Some(concat!(
+                "not allowed, reported errors: ",
+                "init container setup (spec.initContainers[1].image: busybox:latest): tag latest not allowed, registry docker.io not allowed; ",
+                "container app (spec.containers[2].image: docker.io/my-org/app:1.0.0): registry docker.io not allowed; ",
+                "container sidecar (spec.containers[10].image: busybox:latest): tag latest not allowed, registry docker.io not allowed; ",
+                "ephemeral container debugger (spec.ephemeralContainers[0].image: busybox:latest): tag latest not allowed, registry docker.io not allowed",
+            ))
+        );
+    }
+
+    #[rstest]
+    #[case::warn_only(
+        vec![("docker.io", Action::Warn), ("k8s.gcr.io", Action::Warn)],
+        true,
+        None,
+        Some(vec!["container nginx (spec.containers[0].image: nginx): registry docker.io not allowed, deprecated registry k8s.gcr.io, use registry.k8s.io instead"]),
+        None,
+    )]
+    #[case::audit_only(
+        vec![("docker.io", Action::Audit), ("k8s.gcr.io", Action::Audit)],
+        true,
+        None,
+        None,
+        Some("container nginx (spec.containers[0].image: nginx): registry docker.io not allowed, deprecated registry k8s.gcr.io, use registry.k8s.io instead"),
+    )]
+    #[case::mixed_actions(
+        vec![("docker.io", Action::Deny), ("quay.io", Action::Warn), ("k8s.gcr.io", Action::Audit)],
+        false,
+        Some("not allowed, reported errors: container nginx (spec.containers[0].image: nginx): registry docker.io not allowed"),
+        Some(vec!["container nginx (spec.containers[0].image: nginx): registry quay.io not allowed"]),
+        Some("container nginx (spec.containers[0].image: nginx): deprecated registry k8s.gcr.io, use registry.k8s.io instead"),
+    )]
+    fn violations_into_validation_response_by_action(
+        #[case] registries: Vec<(&str, Action)>,
+        #[case] expected_accepted: bool,
+        #[case] expected_message: Option<&str>,
+        #[case] expected_warnings: Option<Vec<&str>>,
+        #[case] expected_audited_violations: Option<&str>,
+    ) {
+        let nginx = container(ContainerType::Container, 0, "nginx", "nginx");
+        let mut rejection_reasons = PodRejectionReasons::default();
+        for (registry, action) in registries {
+            let (violation, rule) = if registry == "k8s.gcr.io" {
+                (
+                    Violation::DeprecatedRegistry {
+                        registry: registry.to_string(),
+                        replacement: "registry.k8s.io".to_string(),
+                    },
+                    "deprecatedRegistries",
+                )
+            } else {
+                (
+                    Violation::RegistryNotAllowed(registry.to_string()),
+                    "registries.reject",
+                )
+            };
+            rejection_reasons.add(&nginx, violation, rule, action);
+        }
+
+        let validation_response = PodSpecValidationResult {
+            rejection_reasons,
+            ..PodSpecValidationResult::default()
+        }
+        .into_response(&RejectionMessage::default());
+
+        assert_eq!(validation_response.accepted, expected_accepted);
+        assert_eq!(validation_response.message.as_deref(), expected_message);
+        assert_eq!(
+            validation_response.warnings,
+            expected_warnings.map(|warnings| warnings.iter().map(|w| w.to_string()).collect())
+        );
+        assert_eq!(
+            validation_response.audit_annotations,
+            expected_audited_violations.map(|violations| HashMap::from([(
+                "violations".to_string(),
+                violations.to_string()
+            )]))
+        );
+    }
+
+    #[rstest]
+    #[case::every_placeholder(
+        "{{containerType}} {{container}} ({{fieldPath}}) uses {{image}} from {{registry}} with tag {{tag}}: {{violation}} [{{rule}}]",
+        vec![
+            "init container setup (spec.initContainers[0].image) uses busybox:1.0 from docker.io with tag 1.0: registry docker.io not allowed [registries.allow]",
+            "container app (spec.containers[0].image) uses quay.io/my-org/app from quay.io with tag latest: registry quay.io not allowed [registries.allow]",
+            "container app (spec.containers[0].image) uses quay.io/my-org/app from quay.io with tag latest: tag latest not allowed [tags.reject]",
+        ]
+    )]
+    #[case::identical_messages_reported_once(
+        "{{image}} is not approved, see https://wiki.corp.com/images",
+        vec![
+            "busybox:1.0 is not approved, see https://wiki.corp.com/images",
+            "quay.io/my-org/app is not approved, see https://wiki.corp.com/images",
+        ]
+    )]
+    fn rejection_message_rendered_from_template(
+        #[case] message_template: &str,
+        #[case] expected_messages: Vec<&str>,
+    ) {
+        let setup = container(ContainerType::InitContainer, 0, "setup", "busybox:1.0");
+        let app = container(ContainerType::Container, 0, "app", "quay.io/my-org/app");
+        let mut rejection_reasons = PodRejectionReasons::default();
+        rejection_reasons.add(
+            &app,
+            Violation::RegistryNotAllowed("quay.io".to_string()),
+            "registries.allow",
+            Action::Deny,
+        );
+        rejection_reasons.add(
+            &app,
+            Violation::TagNotAllowed("latest".to_string()),
+            "tags.reject",
+            Action::Deny,
+        );
+        rejection_reasons.add(
+            &app,
+            Violation::RepositoryNotAllowed("quay.io/my-org/app".to_string()),
+            "repositories.quay.io",
+            Action::Warn,
+        );
+        rejection_reasons.add(
+            &setup,
+            Violation::RegistryNotAllowed("docker.io".to_string()),
+            "registries.allow",
+            Action::Deny,
+        );
+        let rejection_message = RejectionMessage {
+            template: Some(message_template.to_string()),
+            code: Some(403),
+        };
+
+        let validation_response = PodSpecValidationResult {
+            rejection_reasons,
+            ..PodSpecValidationResult::default()
+        }
+        .into_response(&rejection_message);
+
+        assert!(!validation_response.accepted);
+        assert_eq!(
+            validation_response.message,
+            Some(expected_messages.join("; "))
+        );
+        assert_eq!(validation_response.code, Some(403));
+        // warnings keep the built-in format
+        assert_eq!(
+            validation_response.warnings,
+            Some(vec!["container app (spec.containers[0].image: quay.io/my-org/app): repository quay.io/my-org/app not allowed".to_string()])
+        );
+    }
+
+    #[test]
+    fn rejection_message_includes_suggestions() {
+        let nginx = container(ContainerType::Container, 0, "nginx", "nginx:latest");
+        let mut rejection_reasons = PodRejectionReasons::default();
+        rejection_reasons.add(
+            &nginx,
+            Violation::TagNotAllowed("latest".to_string()),
+            "tags.reject",
+            Action::Deny,
+        );
+        rejection_reasons.add(
+            &nginx,
+            Violation::ImageNotAllowed,
+            "images.allow",
+            Action::Deny,
+        );
+        rejection_reasons.containers.get_mut(&nginx).unwrap()[0].suggestion =
+            Some("most recent allowed tag is 1.25.3".to_string());
+        let result = PodSpecValidationResult {
+            rejection_reasons,
+            ..PodSpecValidationResult::default()
+        };
+
+        let default_response = PodSpecValidationResult {
+            rejection_reasons: result.rejection_reasons.clone(),
+            ..PodSpecValidationResult::default()
+        }
+        .into_response(&RejectionMessage::default());
+        let templated_response = result.into_response(&RejectionMessage {
+            template: Some("{{violation}}: {{suggestion}}".to_string()),
+            ..RejectionMessage::default()
+        });
+
+        assert_eq!(
+            default_response.message.as_deref(),
+            Some("not allowed, reported errors: container nginx (spec.containers[0].image: nginx:latest): tag latest not allowed (most recent allowed tag is 1.25.3), image not allowed")
+        );
+        assert_eq!(
+            templated_response.message.as_deref(),
+            Some("tag latest not allowed: most recent allowed tag is 1.25.3; image not allowed: ")
+        );
+    }
+
+    #[rstest]
+    #[case::violation_only(None, None, "tag latest not allowed")]
+    #[case::metadata(
+        Some(RuleMetadata {
+            description: Some("mutable tag".to_string()),
+            owner: Some("platform-team".to_string()),
+            ..RuleMetadata::default()
+        }),
+        None,
+        "tag latest not allowed [mutable tag, owner: platform-team]"
+    )]
+    #[case::metadata_and_suggestion(
+        Some(RuleMetadata {
+            ticket: Some("SEC-42".to_string()),
+            ..RuleMetadata::default()
+        }),
+        Some("most recent allowed tag is 1.25.3"),
+        "tag latest not allowed [ticket: SEC-42] (most recent allowed tag is 1.25.3)"
+    )]
+    fn describe_finding(
+        #[case] metadata: Option<RuleMetadata>,
+        #[case] suggestion: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let finding = Finding {
+            violation: Violation::TagNotAllowed("latest".to_string()),
+            rule: "tags.reject".to_string(),
+            action: Action::Deny,
+            metadata,
+            suggestion: suggestion.map(|suggestion| suggestion.to_string()),
+        };
+
+        assert_eq!(finding.to_string(), expected);
+    }
+
+    #[test]
+    fn rejection_code_not_set_on_accepted_requests() {
+        let rejection_message = RejectionMessage {
+            code: Some(403),
+            ..RejectionMessage::default()
+        };
+
+        let validation_response =
+            PodSpecValidationResult::default().into_response(&rejection_message);
+
+        assert!(validation_response.accepted);
+        assert_eq!(validation_response.code, None);
+    }
+
+    #[test]
+    fn audit_annotations_describe_every_evaluated_image() {
+        let setup = container(ContainerType::InitContainer, 0, "setup", "busybox");
+        let app = container(
+            ContainerType::Container,
+            0,
+            "app",
+            "ghcr.io/my-org/app:1.0.0",
+        );
+        let sidecar = container(
+            ContainerType::Container,
+            1,
+            "sidecar",
+            "quay.io/sidecar:1.0.0",
+        );
+        let invalid = container(ContainerType::Container, 2, "invalid", "INVALID");
+        let models = ContainerImage {
+            pull_policy: Some("IfNotPresent".to_string()),
+            ..container(
+                ContainerType::ImageVolume,
+                1,
+                "models",
+                "quay.io/my-org/models:v1",
+            )
+        };
+        let mut rejection_reasons = PodRejectionReasons::default();
+        rejection_reasons.add(
+            &setup,
+            Violation::TagNotAllowed("latest".to_string()),
+            "tags.reject",
+            Action::Deny,
+        );
+        rejection_reasons.add(
+            &sidecar,
+            Violation::RegistryNotAllowed("quay.io".to_string()),
+            "registries.allow",
+            Action::Warn,
+        );
+        let result = PodSpecValidationResult {
+            evaluated_images: vec![
+                ImageEvaluation {
+                    container: setup,
+                    canonical_image: Some("docker.io/library/busybox:latest".to_string()),
+                    matched_rules: vec!["registries.allow".to_string(), "tags.reject".to_string()],
+                },
+                ImageEvaluation {
+                    container: app,
+                    canonical_image: Some("ghcr.io/my-org/app:1.0.0".to_string()),
+                    matched_rules: vec!["registries.allow".to_string()],
+                },
+                ImageEvaluation {
+                    container: sidecar,
+                    canonical_image: Some("quay.io/sidecar:1.0.0".to_string()),
+                    matched_rules: vec!["registries.allow".to_string()],
+                },
+                ImageEvaluation {
+                    container: invalid,
+                    canonical_image: None,
+                    matched_rules: Vec::new(),
+                },
+                ImageEvaluation {
+                    container: models,
+                    canonical_image: Some("quay.io/my-org/models:v1".to_string()),
+                    matched_rules: vec!["registries.allow".to_string()],
+                },
+            ],
+            rejection_reasons,
+        };
+
+        let validation_response = result.into_response(&RejectionMessage::default());
+
+        assert!(!validation_response.accepted);
+        let audit_annotations = validation_response
+            .audit_annotations
+            .expect("audit annotations not found");
+        let expected: HashMap<String, serde_json::Value> = HashMap::from([
+            (
+                "image.initContainers.0".to_string(),
+                json!({"container": "setup", "image": "docker.io/library/busybox:latest", "decision": "denied", "rules": ["registries.allow", "tags.reject"]}),
+            ),
+            (
+                "image.containers.0".to_string(),
+                json!({"container": "app", "image": "ghcr.io/my-org/app:1.0.0", "decision": "allowed", "rules": ["registries.allow"]}),
+            ),
+            (
+                "image.containers.1".to_string(),
+                json!({"container": "sidecar", "image": "quay.io/sidecar:1.0.0", "decision": "warned", "rules": ["registries.allow"]}),
+            ),
+            (
+                "image.containers.2".to_string(),
+                json!({"container": "invalid", "image": "INVALID", "decision": "allowed", "rules": []}),
+            ),
+            (
+                "image.volumes.1".to_string(),
+                json!({"volume": "models", "image": "quay.io/my-org/models:v1", "pullPolicy": "IfNotPresent", "decision": "allowed", "rules": ["registries.allow"]}),
+            ),
+        ]);
+        let audit_annotations: HashMap<String, serde_json::Value> = audit_annotations
+            .into_iter()
+            .map(|(key, value)| (key, serde_json::from_str(&value).unwrap()))
+            .collect();
+        assert_eq!(audit_annotations, expected);
+    }
 }